@@ -36,6 +36,7 @@ pub mod execution_oracle {
         let cpi_accounts = governance::cpi::accounts::UpdateProposalStatus {
             proposal: ctx.accounts.proposal.to_account_info(),
             dao_config: ctx.accounts.dao_config.to_account_info(),
+            creator_token_owner_record: ctx.accounts.creator_token_owner_record.to_account_info(),
             oracle_authority: ctx.accounts.verifier.to_account_info(),
         };
 
@@ -85,6 +86,10 @@ pub struct VerifySnapshotVote<'info> {
     /// CHECK: This is the DAO config account
     pub dao_config: AccountInfo<'info>,
     
+    /// CHECK: This is the token owner record of the proposal's creator
+    #[account(mut)]
+    pub creator_token_owner_record: AccountInfo<'info>,
+    
     /// CHECK: This is the governance program that will be called
     pub governance_program: AccountInfo<'info>,
 }
@@ -1,9 +1,27 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
 use anchor_spl::token::{self, Token, TokenAccount};
 use anchor_spl::associated_token::AssociatedToken;
+use treasury::program::Treasury as TreasuryProgram;
 
 declare_id!("Govz1VyoyU5Bqe9Xe36hpYT2v7FqCaVQFZ4n2M2XzP1");
 
+/// Voting power of a lock at the maximum duration, relative to an escrowed deposit
+pub const MAX_LOCK_MULTIPLIER: u64 = 4;
+
+/// Number of weight checkpoints kept per token owner; the oldest is dropped when full and no
+/// open proposal can still need it
+pub const MAX_CHECKPOINTS: usize = 16;
+
+/// Delegation scopes tracked per token owner: the general scope plus room for action kinds
+pub const MAX_DELEGATION_SCOPES: usize = 8;
+
+/// Bounds on the named options of a multiple-choice proposal
+pub const MIN_OPTIONS: usize = 2;
+pub const MAX_OPTIONS: usize = 10;
+pub const MAX_OPTION_LABEL_LEN: usize = 32;
+
 #[program]
 pub mod governance {
     use super::*;
@@ -15,68 +33,284 @@ pub mod governance {
         let dao_config = &mut ctx.accounts.dao_config;
         dao_config.authority = ctx.accounts.authority.key();
         dao_config.token_mint = ctx.accounts.token_mint.key();
-        dao_config.proposal_fee = config.proposal_fee;
+        dao_config.proposal_deposit = config.proposal_deposit;
         dao_config.voting_period = config.voting_period;
+        dao_config.max_voting_period = config.voting_period;
         dao_config.quorum_percentage = config.quorum_percentage;
         dao_config.threshold_percentage = config.threshold_percentage;
         dao_config.require_poh = config.require_poh;
+        dao_config.max_lock_duration = config.max_lock_duration;
+        dao_config.max_delegation_depth = config.max_delegation_depth;
+        dao_config.voting_delay = config.voting_delay;
+        dao_config.timelock_delay = config.timelock_delay;
+        dao_config.grace_period = config.grace_period;
+        dao_config.guardian = config.guardian;
+        dao_config.refund_deposit_on_cancel = config.refund_deposit_on_cancel;
+        dao_config.deposit_participation_percentage = config.deposit_participation_percentage;
+        dao_config.allow_vote_change = config.allow_vote_change;
+        dao_config.min_tokens_to_create_proposal = config.min_tokens_to_create_proposal;
+        dao_config.max_active_proposals_per_creator = config.max_active_proposals_per_creator;
         dao_config.bump = *ctx.bumps.get("dao_config").unwrap();
         Ok(())
     }
 
-    pub fn create_proposal(
-        ctx: Context<CreateProposal>,
+    pub fn create_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateProposal<'info>>,
         title: String,
         description: String,
         snapshot_id: String,
-        actions: Vec<ProposedAction>,
+        vote_type: VoteType,
+        options: Vec<String>,
     ) -> Result<()> {
+        // Yes/no proposals use the fixed For/Against/Abstain options
+        let options_valid = match vote_type {
+            VoteType::YesNo => options.is_empty(),
+            VoteType::SingleChoice | VoteType::Approval => {
+                (MIN_OPTIONS..=MAX_OPTIONS).contains(&options.len())
+                    && options.iter().all(|label| label.len() <= MAX_OPTION_LABEL_LEN)
+            }
+        };
+        require!(options_valid, GovernanceError::InvalidOptions);
+
+        // The creator needs enough escrowed weight they have not delegated away, plus general
+        // delegations they hold passed as remaining accounts, and room under the cap on
+        // unresolved proposals
+        let dao_config = &mut ctx.accounts.dao_config;
+        let creator_token_owner_record = &mut ctx.accounts.creator_token_owner_record;
+        let current_time = Clock::get()?.unix_timestamp;
+        let creator_weight = creator_token_owner_record
+            .undelegated_weight(current_time, dao_config.max_lock_duration)
+            .checked_add(held_delegation_weight(
+                ctx.remaining_accounts,
+                dao_config.key(),
+                ctx.accounts.creator.key(),
+                current_time,
+            )?)
+            .unwrap();
+        require!(
+            creator_weight >= dao_config.min_tokens_to_create_proposal,
+            GovernanceError::InsufficientWeightToCreateProposal
+        );
+        require!(
+            creator_token_owner_record.active_proposal_count < dao_config.max_active_proposals_per_creator,
+            GovernanceError::TooManyActiveProposals
+        );
+        creator_token_owner_record.active_proposal_count += 1;
+
         let proposal = &mut ctx.accounts.proposal;
         proposal.creator = ctx.accounts.creator.key();
+        proposal.dao = dao_config.key();
+        proposal.id = dao_config.proposal_count;
         proposal.title = title;
         proposal.description = description;
         proposal.snapshot_id = snapshot_id;
-        proposal.actions = actions;
         proposal.creation_time = Clock::get()?.unix_timestamp;
-        proposal.voting_start_time = Clock::get()?.unix_timestamp;
-        proposal.voting_end_time = Clock::get()?.unix_timestamp + ctx.accounts.dao_config.voting_period;
-        proposal.status = ProposalStatus::Active;
+        // Drafts are edited and their actions inserted until every signatory signs off
+        proposal.status = ProposalStatus::Draft;
         proposal.for_votes = 0;
         proposal.against_votes = 0;
         proposal.abstain_votes = 0;
+        proposal.vote_type = vote_type;
+        proposal.options = options
+            .into_iter()
+            .map(|label| ProposalOption { label, vote_weight: 0 })
+            .collect();
+        proposal.choice_votes = 0;
+        proposal.winning_option = 0;
+        proposal.deposit_amount = dao_config.proposal_deposit;
+        proposal.deposit_refundable = false;
         proposal.bump = *ctx.bumps.get("proposal").unwrap();
 
-        // Transfer proposal fee if required
-        if ctx.accounts.dao_config.proposal_fee > 0 {
+        dao_config.proposal_count = dao_config.proposal_count.checked_add(1).unwrap();
+        emit!(ProposalCreated {
+            dao: proposal.dao,
+            proposal: proposal.key(),
+            id: proposal.id,
+            creator: proposal.creator,
+        });
+
+        // Escrow the proposal deposit until the outcome decides whether it is refunded
+        if ctx.accounts.dao_config.proposal_deposit > 0 {
             token::transfer(
                 CpiContext::new(
                     ctx.accounts.token_program.to_account_info(),
                     token::Transfer {
                         from: ctx.accounts.creator_token_account.to_account_info(),
-                        to: ctx.accounts.dao_token_account.to_account_info(),
+                        to: ctx.accounts.proposal_deposit.to_account_info(),
                         authority: ctx.accounts.creator.to_account_info(),
                     },
                 ),
-                ctx.accounts.dao_config.proposal_fee,
+                ctx.accounts.dao_config.proposal_deposit,
             )?;
         }
 
         Ok(())
     }
 
-    pub fn cast_vote(
-        ctx: Context<CastVote>,
+    pub fn update_proposal(
+        ctx: Context<UpdateProposal>,
+        title: String,
+        description: String,
+        snapshot_id: String,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.is_editable(),
+            GovernanceError::ProposalNotEditable
+        );
+        require!(
+            proposal.creator == ctx.accounts.creator.key(),
+            GovernanceError::Unauthorized
+        );
+
+        proposal.title = title;
+        proposal.description = description;
+        proposal.snapshot_id = snapshot_id;
+        Ok(())
+    }
+
+    pub fn add_signatory(
+        ctx: Context<AddSignatory>,
+        signatory: Pubkey,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.is_editable(),
+            GovernanceError::ProposalNotEditable
+        );
+        require!(
+            proposal.creator == ctx.accounts.creator.key(),
+            GovernanceError::Unauthorized
+        );
+
+        let signatory_record = &mut ctx.accounts.signatory_record;
+        signatory_record.proposal = proposal.key();
+        signatory_record.signatory = signatory;
+        signatory_record.signed_off = false;
+        signatory_record.bump = *ctx.bumps.get("signatory_record").unwrap();
+
+        proposal.signatories_count = proposal.signatories_count.checked_add(1).unwrap();
+        Ok(())
+    }
+
+    pub fn insert_transaction(
+        ctx: Context<InsertTransaction>,
+        option_index: u8,
+        action: ProposedAction,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.is_editable(),
+            GovernanceError::ProposalNotEditable
+        );
+        require!(
+            proposal.creator == ctx.accounts.creator.key(),
+            GovernanceError::Unauthorized
+        );
+        // Each option has its own actions; those of a yes/no proposal run when it passes
+        require!(
+            (option_index as usize) < proposal.option_count(),
+            GovernanceError::InvalidOptionIndex
+        );
+
+        let proposal_transaction = &mut ctx.accounts.proposal_transaction;
+        proposal_transaction.proposal = proposal.key();
+        proposal_transaction.option_index = option_index;
+        proposal_transaction.index = proposal.transaction_counts[option_index as usize];
+        proposal_transaction.executed = false;
+        proposal_transaction.bump = *ctx.bumps.get("proposal_transaction").unwrap();
+
+        proposal.transaction_counts[option_index as usize] = proposal_transaction.index.checked_add(1).unwrap();
+        proposal.action_kind_counts[action.kind() as usize] += 1;
+        proposal_transaction.action = action;
+        Ok(())
+    }
+
+    pub fn remove_transaction(
+        ctx: Context<RemoveTransaction>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.is_editable(),
+            GovernanceError::ProposalNotEditable
+        );
+        require!(
+            proposal.creator == ctx.accounts.creator.key(),
+            GovernanceError::Unauthorized
+        );
+
+        // Transactions are removed from the end of their option so indices stay contiguous
+        let proposal_transaction = &ctx.accounts.proposal_transaction;
+        let transaction_count = &mut proposal.transaction_counts[proposal_transaction.option_index as usize];
+        require!(
+            proposal_transaction.index + 1 == *transaction_count,
+            GovernanceError::NotLastTransaction
+        );
+        *transaction_count -= 1;
+        proposal.action_kind_counts[proposal_transaction.action.kind() as usize] -= 1;
+        Ok(())
+    }
+
+    pub fn sign_off_proposal(
+        ctx: Context<SignOffProposal>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Draft,
+            GovernanceError::ProposalNotDraft
+        );
+
+        // Without signatories the creator signs off; otherwise every signatory must
+        let signatory = ctx.accounts.signatory.key();
+        match &mut ctx.accounts.signatory_record {
+            None => {
+                require!(
+                    proposal.signatories_count == 0 && proposal.creator == signatory,
+                    GovernanceError::Unauthorized
+                );
+            }
+            Some(signatory_record) => {
+                require!(
+                    signatory_record.signatory == signatory,
+                    GovernanceError::Unauthorized
+                );
+                require!(
+                    !signatory_record.signed_off,
+                    GovernanceError::AlreadySignedOff
+                );
+                signatory_record.signed_off = true;
+                proposal.signatories_signed_off_count += 1;
+                if proposal.signatories_signed_off_count < proposal.signatories_count {
+                    return Ok(());
+                }
+            }
+        }
+
+        // Voting opens after the delay, and weight is counted as it stood just before then
+        let dao_config = &mut ctx.accounts.dao_config;
+        proposal.voting_start_time = Clock::get()?.unix_timestamp + dao_config.voting_delay;
+        proposal.voting_end_time = proposal.voting_start_time + dao_config.voting_period;
+        proposal.status = ProposalStatus::Pending;
+        dao_config.latest_voting_start_time = dao_config.latest_voting_start_time.max(proposal.voting_start_time);
+        Ok(())
+    }
+
+    pub fn cast_vote<'info>(
+        ctx: Context<'_, '_, '_, 'info, CastVote<'info>>,
         vote_option: VoteOption,
-        voter_token_amount: u64,
     ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         let vote_record = &mut ctx.accounts.vote_record;
         
-        // Verify proposal is active
+        // Verify proposal is open for voting
         require!(
-            proposal.status == ProposalStatus::Active,
+            proposal.is_open(),
             GovernanceError::ProposalNotActive
         );
+        require!(
+            proposal.is_valid_vote(vote_option),
+            GovernanceError::InvalidVoteOption
+        );
 
         // Verify voting period
         let current_time = Clock::get()?.unix_timestamp;
@@ -85,33 +319,439 @@ pub mod governance {
             GovernanceError::NotInVotingPeriod
         );
 
+        // The first vote after the voting delay activates the proposal
+        if proposal.status == ProposalStatus::Pending {
+            proposal.status = ProposalStatus::Active;
+        }
+
+        // A delegatee may already have cast the voter's delegated weight; voting directly
+        // overrides the delegatee and moves that weight to the voter's own choice
+        let voter = ctx.accounts.voter.key();
+        if vote_record.voter != Pubkey::default() {
+            require!(vote_record.cast_by != voter, GovernanceError::AlreadyVoted);
+            let delegate_vote_record = ctx.accounts.delegate_vote_record
+                .as_mut()
+                .ok_or(GovernanceError::InvalidDelegateVoteRecord)?;
+            require!(
+                delegate_vote_record.voter == vote_record.cast_by,
+                GovernanceError::InvalidDelegateVoteRecord
+            );
+            delegate_vote_record.release_delegated_weight(proposal, vote_record.token_amount);
+        } else {
+            // The delegate's record is only loaded to override them, since it is written back
+            require!(
+                ctx.accounts.delegate_vote_record.is_none(),
+                GovernanceError::InvalidDelegateVoteRecord
+            );
+        }
+
+        // Voting weight is the voter's escrowed deposit and lock power from before voting
+        // opened, plus any delegations they held by then, passed as remaining accounts
+        let voter_weight = ctx.accounts.token_owner_record.voter_weight_at(
+            proposal.voting_start_time,
+            ctx.accounts.dao_config.max_lock_duration,
+        );
+        let delegated_weight = cast_delegated_votes(
+            ctx.remaining_accounts,
+            proposal,
+            &mut ctx.accounts.delegate_vote_record,
+            ctx.accounts.dao_config.max_delegation_depth,
+            &DelegatedVote {
+                voter: &ctx.accounts.voter.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+                vote_option,
+            },
+            current_time,
+        )?;
+        require!(
+            voter_weight > 0 || delegated_weight > 0,
+            GovernanceError::NoVotingWeight
+        );
+
         // Record vote
         vote_record.proposal = proposal.key();
-        vote_record.voter = ctx.accounts.voter.key();
+        vote_record.voter = voter;
         vote_record.vote_option = vote_option;
-        vote_record.token_amount = voter_token_amount;
+        vote_record.token_amount = voter_weight;
         vote_record.timestamp = current_time;
+        vote_record.is_relinquished = false;
+        if vote_record.payer == Pubkey::default() {
+            vote_record.payer = voter;
+        }
+        vote_record.cast_by = voter;
+        vote_record.delegation_depth = 0;
+        vote_record.delegated_weight = delegated_weight;
+        vote_record.is_withdrawn = false;
+        vote_record.change_count = 0;
         vote_record.bump = *ctx.bumps.get("vote_record").unwrap();
 
         // Update proposal vote counts
-        match vote_option {
-            VoteOption::For => proposal.for_votes = proposal.for_votes.checked_add(voter_token_amount).unwrap(),
-            VoteOption::Against => proposal.against_votes = proposal.against_votes.checked_add(voter_token_amount).unwrap(),
-            VoteOption::Abstain => proposal.abstain_votes = proposal.abstain_votes.checked_add(voter_token_amount).unwrap(),
+        proposal.add_votes(vote_option, voter_weight.checked_add(delegated_weight).unwrap());
+
+        // Lock the deposit until the vote is relinquished
+        let token_owner_record = &mut ctx.accounts.token_owner_record;
+        token_owner_record.outstanding_vote_count = token_owner_record.outstanding_vote_count.checked_add(1).unwrap();
+
+        Ok(())
+    }
+
+    pub fn change_vote(
+        ctx: Context<ChangeVote>,
+        vote_option: VoteOption,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let vote_record = &mut ctx.accounts.vote_record;
+        require_vote_change_allowed(proposal, vote_record, ctx.accounts.dao_config.allow_vote_change)?;
+        require!(
+            proposal.is_valid_vote(vote_option),
+            GovernanceError::InvalidVoteOption
+        );
+
+        // The voter's own weight and the delegated weight they cast move together; a withdrawn
+        // vote is counted again
+        let weight = vote_record.cast_weight();
+        if !vote_record.is_withdrawn {
+            proposal.remove_votes(vote_record.vote_option, weight);
+        }
+        proposal.add_votes(vote_option, weight);
+
+        let previous_option = vote_record.vote_option;
+        vote_record.vote_option = vote_option;
+        vote_record.is_withdrawn = false;
+        vote_record.timestamp = Clock::get()?.unix_timestamp;
+        vote_record.change_count = vote_record.change_count.saturating_add(1);
+        emit!(VoteChanged {
+            proposal: proposal.key(),
+            voter: vote_record.voter,
+            previous_option,
+            vote_option,
+            weight,
+            timestamp: vote_record.timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn withdraw_vote(
+        ctx: Context<ChangeVote>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let vote_record = &mut ctx.accounts.vote_record;
+        require_vote_change_allowed(proposal, vote_record, ctx.accounts.dao_config.allow_vote_change)?;
+        require!(
+            !vote_record.is_withdrawn,
+            GovernanceError::VoteAlreadyWithdrawn
+        );
+
+        // The record is kept so delegations cast through it stay counted once
+        let weight = vote_record.cast_weight();
+        proposal.remove_votes(vote_record.vote_option, weight);
+        vote_record.is_withdrawn = true;
+        vote_record.timestamp = Clock::get()?.unix_timestamp;
+        vote_record.change_count = vote_record.change_count.saturating_add(1);
+        emit!(VoteWithdrawn {
+            proposal: proposal.key(),
+            voter: vote_record.voter,
+            vote_option: vote_record.vote_option,
+            weight,
+            timestamp: vote_record.timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn relinquish_vote(
+        ctx: Context<RelinquishVote>,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let vote_record = &mut ctx.accounts.vote_record;
+        require!(
+            !proposal.is_open(),
+            GovernanceError::ProposalStillActive
+        );
+        let is_settled = proposal.is_settled();
+        require!(
+            is_settled || !vote_record.is_relinquished,
+            GovernanceError::VoteAlreadyRelinquished
+        );
+        // Anyone can close the record once the outcome is settled; relinquishing earlier to
+        // unlock the deposit takes the voter's signature
+        require!(
+            is_settled || ctx.accounts.voter.as_ref().map_or(false, |voter| voter.key() == vote_record.voter),
+            GovernanceError::Unauthorized
+        );
+
+        // Only direct votes lock the voter's deposit
+        if vote_record.cast_by == vote_record.voter && !vote_record.is_relinquished {
+            let token_owner_record = &mut ctx.accounts.token_owner_record;
+            token_owner_record.outstanding_vote_count = token_owner_record.outstanding_vote_count.checked_sub(1).unwrap();
+        }
+        vote_record.is_relinquished = true;
+
+        // Once the outcome can no longer change the record is closed and its rent returned
+        if is_settled {
+            vote_record.close(ctx.accounts.payer.to_account_info())?;
         }
+        Ok(())
+    }
+
+    pub fn deposit_governing_tokens(
+        ctx: Context<DepositGoverningTokens>,
+        amount: u64,
+    ) -> Result<()> {
+        // Escrow tokens in the DAO vault
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    to: ctx.accounts.governing_token_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let token_owner_record = &mut ctx.accounts.token_owner_record;
+        token_owner_record.owner = ctx.accounts.owner.key();
+        token_owner_record.governing_token_deposit_amount = token_owner_record.governing_token_deposit_amount.checked_add(amount).unwrap();
+        token_owner_record.bump = *ctx.bumps.get("token_owner_record").unwrap();
+
+        let deposit_amount = token_owner_record.governing_token_deposit_amount;
+        token_owner_record.record_checkpoint(Clock::get()?.unix_timestamp, &ctx.accounts.dao_config, |checkpoint| {
+            checkpoint.deposit_amount = deposit_amount;
+        })?;
+        Ok(())
+    }
+
+    pub fn withdraw_governing_tokens(
+        ctx: Context<WithdrawGoverningTokens>,
+        amount: u64,
+    ) -> Result<()> {
+        let token_owner_record = &mut ctx.accounts.token_owner_record;
+        require!(
+            token_owner_record.outstanding_vote_count == 0,
+            GovernanceError::OutstandingVotes
+        );
+        require!(
+            amount <= token_owner_record.undelegated_deposit_amount(),
+            GovernanceError::InsufficientDeposit
+        );
+        token_owner_record.governing_token_deposit_amount -= amount;
+
+        let deposit_amount = token_owner_record.governing_token_deposit_amount;
+        token_owner_record.record_checkpoint(Clock::get()?.unix_timestamp, &ctx.accounts.dao_config, |checkpoint| {
+            checkpoint.deposit_amount = deposit_amount;
+        })?;
+
+        // Release tokens from the DAO vault
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.governing_token_vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.dao_config.to_account_info(),
+                },
+                &[&[
+                    b"dao_config",
+                    ctx.accounts.dao_config.token_mint.as_ref(),
+                    ctx.accounts.dao_config.authority.as_ref(),
+                    &[ctx.accounts.dao_config.bump],
+                ]],
+            ),
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn create_lock(
+        ctx: Context<CreateLock>,
+        amount: u64,
+        lock_duration: i64,
+    ) -> Result<()> {
+        require!(amount > 0, GovernanceError::InvalidLockAmount);
+        require!(
+            lock_duration > 0 && lock_duration <= ctx.accounts.dao_config.max_lock_duration,
+            GovernanceError::InvalidLockDuration
+        );
+
+        // Escrow the locked tokens in the DAO vault
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    to: ctx.accounts.governing_token_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let vote_lock = &mut ctx.accounts.vote_lock;
+        vote_lock.owner = ctx.accounts.owner.key();
+        vote_lock.amount = amount;
+        vote_lock.lock_end = current_time.checked_add(lock_duration).unwrap();
+        vote_lock.bump = *ctx.bumps.get("vote_lock").unwrap();
+
+        // The owner record tracks outstanding votes and the weight history of the lock
+        let token_owner_record = &mut ctx.accounts.token_owner_record;
+        token_owner_record.owner = ctx.accounts.owner.key();
+        token_owner_record.bump = *ctx.bumps.get("token_owner_record").unwrap();
+        token_owner_record.record_lock_checkpoint(Clock::get()?.unix_timestamp, &ctx.accounts.dao_config, vote_lock)?;
+        Ok(())
+    }
+
+    pub fn increase_amount(
+        ctx: Context<IncreaseLockAmount>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GovernanceError::InvalidLockAmount);
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time < ctx.accounts.vote_lock.lock_end,
+            GovernanceError::LockExpired
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    to: ctx.accounts.governing_token_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let vote_lock = &mut ctx.accounts.vote_lock;
+        vote_lock.amount = vote_lock.amount.checked_add(amount).unwrap();
+        ctx.accounts.token_owner_record.record_lock_checkpoint(Clock::get()?.unix_timestamp, &ctx.accounts.dao_config, vote_lock)?;
+        Ok(())
+    }
+
+    pub fn extend_lock(
+        ctx: Context<ExtendLock>,
+        new_lock_end: i64,
+    ) -> Result<()> {
+        let vote_lock = &mut ctx.accounts.vote_lock;
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time < vote_lock.lock_end, GovernanceError::LockExpired);
+        require!(
+            new_lock_end > vote_lock.lock_end
+                && new_lock_end <= current_time.checked_add(ctx.accounts.dao_config.max_lock_duration).unwrap(),
+            GovernanceError::InvalidLockDuration
+        );
+
+        vote_lock.lock_end = new_lock_end;
+        ctx.accounts.token_owner_record.record_lock_checkpoint(Clock::get()?.unix_timestamp, &ctx.accounts.dao_config, vote_lock)?;
+        Ok(())
+    }
+
+    pub fn withdraw_expired(
+        ctx: Context<WithdrawExpired>,
+    ) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.vote_lock.lock_end,
+            GovernanceError::LockNotExpired
+        );
+        require!(
+            ctx.accounts.token_owner_record.outstanding_vote_count == 0,
+            GovernanceError::OutstandingVotes
+        );
+
+        // Release the locked tokens; the lock account is closed to the owner
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.governing_token_vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.dao_config.to_account_info(),
+                },
+                &[&[
+                    b"dao_config",
+                    ctx.accounts.dao_config.token_mint.as_ref(),
+                    ctx.accounts.dao_config.authority.as_ref(),
+                    &[ctx.accounts.dao_config.bump],
+                ]],
+            ),
+            ctx.accounts.vote_lock.amount,
+        )?;
 
+        ctx.accounts.token_owner_record.record_checkpoint(Clock::get()?.unix_timestamp, &ctx.accounts.dao_config, |checkpoint| {
+            checkpoint.lock_amount = 0;
+            checkpoint.lock_end = 0;
+        })?;
         Ok(())
     }
 
-    pub fn delegate_votes(
-        ctx: Context<DelegateVotes>,
+    pub fn delegate_votes<'info>(
+        ctx: Context<'_, '_, '_, 'info, DelegateVotes<'info>>,
         amount: u64,
+        scope: Option<ActionKind>,
+        expires_at: Option<i64>,
     ) -> Result<()> {
+        let delegator = ctx.accounts.delegator.key();
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.delegatee.key() != delegator,
+            GovernanceError::InvalidDelegation
+        );
+        require!(
+            expires_at.map_or(true, |expires_at| expires_at > current_time),
+            GovernanceError::InvalidDelegationExpiry
+        );
+
+        // Walk the delegatee's outgoing delegations in the same scope, passed as remaining
+        // accounts, and reject the delegation if the chain leads back to the delegator within
+        // the depth limit
+        let dao = ctx.accounts.dao_config.key();
+        let scope_index = delegation_scope_index(scope);
+        let mut current = ctx.accounts.delegatee.key();
+        for hop in 0..ctx.accounts.dao_config.max_delegation_depth as usize {
+            let delegation_info = ctx.remaining_accounts
+                .get(hop)
+                .ok_or(GovernanceError::InvalidDelegationChain)?;
+            let (expected_delegation, _) = Pubkey::find_program_address(
+                &[b"delegation", dao.as_ref(), current.as_ref(), &[scope_index]],
+                ctx.program_id,
+            );
+            require!(
+                delegation_info.key() == expected_delegation,
+                GovernanceError::InvalidDelegationChain
+            );
+            if delegation_info.data_is_empty() {
+                break;
+            }
+
+            let next = Account::<Delegation>::try_from(delegation_info)?;
+            if next.is_expired(current_time) {
+                break;
+            }
+            require!(next.delegatee != delegator, GovernanceError::DelegationCycle);
+            current = next.delegatee;
+        }
+
+        // Delegated weight must be backed by the delegator's escrowed deposit. Delegations in
+        // different scopes never count on the same proposal, so each may use the full deposit.
+        let token_owner_record = &mut ctx.accounts.token_owner_record;
+        require!(
+            amount > 0 && amount <= token_owner_record.governing_token_deposit_amount,
+            GovernanceError::InsufficientDeposit
+        );
+        token_owner_record.delegated_amounts[scope_index as usize] = amount;
+
         let delegation = &mut ctx.accounts.delegation;
-        delegation.delegator = ctx.accounts.delegator.key();
+        delegation.dao = dao;
+        delegation.delegator = delegator;
         delegation.delegatee = ctx.accounts.delegatee.key();
         delegation.amount = amount;
-        delegation.timestamp = Clock::get()?.unix_timestamp;
+        delegation.scope = scope;
+        delegation.expires_at = expires_at;
+        delegation.timestamp = current_time;
         delegation.bump = *ctx.bumps.get("delegation").unwrap();
         Ok(())
     }
@@ -119,12 +759,29 @@ pub mod governance {
     pub fn revoke_delegation(
         ctx: Context<RevokeDelegation>,
     ) -> Result<()> {
-        let delegation = &mut ctx.accounts.delegation;
+        let delegation = &ctx.accounts.delegation;
         require!(
             delegation.delegator == ctx.accounts.delegator.key(),
             GovernanceError::Unauthorized
         );
-        delegation.amount = 0;
+
+        // Release the deposit backing the delegation; the account is closed to the delegator
+        // so the same scope can be delegated again
+        ctx.accounts.token_owner_record.delegated_amounts[delegation_scope_index(delegation.scope) as usize] = 0;
+        Ok(())
+    }
+
+    pub fn close_delegation(
+        ctx: Context<CloseDelegation>,
+    ) -> Result<()> {
+        let delegation = &ctx.accounts.delegation;
+        require!(
+            delegation.is_expired(Clock::get()?.unix_timestamp),
+            GovernanceError::DelegationNotExpired
+        );
+
+        // Release the deposit backing the delegation; rent is returned to the delegator
+        ctx.accounts.token_owner_record.delegated_amounts[delegation_scope_index(delegation.scope) as usize] = 0;
         Ok(())
     }
 
@@ -134,9 +791,13 @@ pub mod governance {
     ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         require!(
-            proposal.status == ProposalStatus::Active,
+            proposal.is_open(),
             GovernanceError::ProposalNotActive
         );
+        require!(
+            proposal.vote_type == VoteType::YesNo,
+            GovernanceError::InvalidVoteType
+        );
 
         // Verify the oracle's authority
         require!(
@@ -149,12 +810,42 @@ pub mod governance {
             SnapshotOutcome::Passed => ProposalStatus::Succeeded,
             SnapshotOutcome::Failed => ProposalStatus::Defeated,
         };
+        proposal.deposit_refundable = true;
+        ctx.accounts.creator_token_owner_record.active_proposal_count -= 1;
+
+        Ok(())
+    }
+
+    pub fn finalize_proposal(
+        ctx: Context<FinalizeProposal>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.is_open(),
+            GovernanceError::ProposalNotActive
+        );
+        require!(
+            Clock::get()?.unix_timestamp > proposal.voting_end_time,
+            GovernanceError::VotingNotEnded
+        );
+
+        // The deposit is slashed when a defeated proposal drew too few votes
+        let dao_config = &ctx.accounts.dao_config;
+        let supply = ctx.accounts.governing_token_mint.supply;
+        (proposal.status, proposal.winning_option) = proposal.tally(
+            supply,
+            dao_config.quorum_percentage,
+            dao_config.threshold_percentage,
+        );
+        proposal.deposit_refundable = proposal.status == ProposalStatus::Succeeded
+            || proposal.participation_reached(supply, dao_config.deposit_participation_percentage);
+        ctx.accounts.creator_token_owner_record.active_proposal_count -= 1;
 
         Ok(())
     }
 
-    pub fn execute_proposal(
-        ctx: Context<ExecuteProposal>,
+    pub fn queue_proposal(
+        ctx: Context<QueueProposal>,
     ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         require!(
@@ -162,46 +853,612 @@ pub mod governance {
             GovernanceError::ProposalNotSucceeded
         );
 
-        // Execute each action in the proposal
-        for action in &proposal.actions {
-            match action {
-                ProposedAction::TreasuryTransfer { recipient, amount, token_mint } => {
-                    // Execute treasury transfer
-                    // This would be implemented in the treasury program
-                },
-                ProposedAction::UpdateConfig { new_config } => {
-                    // Update DAO configuration
-                    let dao_config = &mut ctx.accounts.dao_config;
-                    dao_config.voting_period = new_config.voting_period;
-                    dao_config.quorum_percentage = new_config.quorum_percentage;
-                    dao_config.threshold_percentage = new_config.threshold_percentage;
-                },
-            }
+        // A winning option without transactions has nothing to wait for
+        if proposal.transaction_counts[proposal.winning_option as usize] == 0 {
+            proposal.status = ProposalStatus::Executed;
+            return Ok(());
         }
 
-        proposal.status = ProposalStatus::Executed;
+        // Give token holders time to react before the proposal can be executed
+        proposal.eta = Clock::get()?.unix_timestamp
+            .checked_add(ctx.accounts.dao_config.timelock_delay)
+            .unwrap();
+        proposal.status = ProposalStatus::Queued;
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializeDao<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = DaoConfig::LEN,
-        seeds = [b"dao_config"],
-        bump
-    )]
-    pub dao_config: Account<'info, DaoConfig>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub token_mint: Account<'info, token::Mint>,
-    
-    pub system_program: Program<'info, System>,
-}
+    pub fn expire_proposal(
+        ctx: Context<ExpireProposal>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Queued,
+            GovernanceError::ProposalNotQueued
+        );
+        require!(
+            Clock::get()?.unix_timestamp > proposal.grace_period_end(ctx.accounts.dao_config.grace_period),
+            GovernanceError::ProposalNotExpired
+        );
+
+        proposal.status = ProposalStatus::Expired;
+        Ok(())
+    }
+
+    pub fn cancel_proposal(
+        ctx: Context<CancelProposal>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.is_open() || proposal.status == ProposalStatus::Draft,
+            GovernanceError::ProposalNotActive
+        );
+        require!(
+            proposal.creator == ctx.accounts.creator.key(),
+            GovernanceError::Unauthorized
+        );
+        proposal.status = ProposalStatus::Canceled;
+        proposal.deposit_refundable = ctx.accounts.dao_config.refund_deposit_on_cancel;
+        ctx.accounts.creator_token_owner_record.active_proposal_count -= 1;
+        Ok(())
+    }
+
+    pub fn veto_proposal(
+        ctx: Context<VetoProposal>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Queued,
+            GovernanceError::ProposalNotQueued
+        );
+        require!(
+            ctx.accounts.guardian.key() == ctx.accounts.dao_config.guardian,
+            GovernanceError::Unauthorized
+        );
+
+        // Vetoed proposals forfeit their deposit
+        proposal.status = ProposalStatus::Vetoed;
+        proposal.deposit_refundable = false;
+        Ok(())
+    }
+
+    pub fn release_deposit(
+        ctx: Context<ReleaseDeposit>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        // A succeeded or queued proposal can still be vetoed, which forfeits the deposit
+        require!(
+            proposal.is_settled(),
+            GovernanceError::ProposalNotSettled
+        );
+
+        // Refund the creator or slash the deposit to the DAO's treasury, then close the escrow
+        let destination = if proposal.deposit_refundable {
+            ctx.accounts.creator_token_account.to_account_info()
+        } else {
+            ctx.accounts.treasury_token_account
+                .as_ref()
+                .ok_or(GovernanceError::MissingTreasuryTokenAccount)?
+                .to_account_info()
+        };
+        let id_bytes = proposal.id.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[
+            b"proposal",
+            proposal.dao.as_ref(),
+            &id_bytes,
+            &[proposal.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.proposal_deposit.to_account_info(),
+                    to: destination,
+                    authority: proposal.to_account_info(),
+                },
+                &[signer_seeds],
+            ),
+            ctx.accounts.proposal_deposit.amount,
+        )?;
+        token::close_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::CloseAccount {
+                    account: ctx.accounts.proposal_deposit.to_account_info(),
+                    destination: ctx.accounts.creator.to_account_info(),
+                    authority: proposal.to_account_info(),
+                },
+                &[signer_seeds],
+            ),
+        )?;
+
+        proposal.deposit_amount = 0;
+        Ok(())
+    }
+
+    pub fn execute_transaction<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTransaction<'info>>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Queued,
+            GovernanceError::ProposalNotQueued
+        );
+
+        // Executable only between the eta and the end of the grace period
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= proposal.eta,
+            GovernanceError::TimelockNotElapsed
+        );
+        require!(
+            current_time <= proposal.grace_period_end(ctx.accounts.dao_config.grace_period),
+            GovernanceError::ProposalExpired
+        );
+
+        // Only the winning option's transactions run, in order and at most once each
+        let proposal_transaction = &mut ctx.accounts.proposal_transaction;
+        require!(
+            proposal_transaction.option_index == proposal.winning_option,
+            GovernanceError::OptionNotWinning
+        );
+        require!(
+            !proposal_transaction.executed,
+            GovernanceError::ActionAlreadyExecuted
+        );
+        require!(
+            proposal_transaction.index == proposal.executed_transaction_count,
+            GovernanceError::ActionOutOfOrder
+        );
+
+        // Treasury transfers and instructions take their accounts from the remaining accounts
+        let mut remaining_accounts = ctx.remaining_accounts.iter();
+        match &proposal_transaction.action {
+            ProposedAction::TreasuryTransfer { recipient, amount, token_mint } => {
+                execute_treasury_transfer(
+                    &mut remaining_accounts,
+                    &ctx.accounts.dao_config,
+                    &ctx.accounts.treasury_program,
+                    &ctx.accounts.token_program,
+                    *recipient,
+                    *amount,
+                    *token_mint,
+                )?;
+            },
+            ProposedAction::UpdateConfig { new_config } => {
+                // Update DAO configuration
+                let dao_config = &mut ctx.accounts.dao_config;
+                dao_config.voting_period = new_config.voting_period;
+                dao_config.max_voting_period = dao_config.max_voting_period.max(new_config.voting_period);
+                dao_config.quorum_percentage = new_config.quorum_percentage;
+                dao_config.threshold_percentage = new_config.threshold_percentage;
+            },
+            ProposedAction::SetGuardian { guardian } => {
+                // The guardian can only be replaced through governance
+                ctx.accounts.dao_config.guardian = *guardian;
+            },
+            ProposedAction::Instruction { program_id, accounts, data } => {
+                execute_instruction(
+                    &mut remaining_accounts,
+                    ctx.accounts.dao_config.key(),
+                    *ctx.bumps.get("native_treasury").unwrap(),
+                    *program_id,
+                    accounts,
+                    data,
+                )?;
+            },
+        }
+
+        proposal_transaction.executed = true;
+        proposal.executed_transaction_count += 1;
+        if proposal.executed_transaction_count == proposal.transaction_counts[proposal.winning_option as usize] {
+            proposal.status = ProposalStatus::Executed;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `vote_record` is a direct vote that may still be changed
+fn require_vote_change_allowed(proposal: &Proposal, vote_record: &VoteRecord, allow_vote_change: bool) -> Result<()> {
+    require!(allow_vote_change, GovernanceError::VoteChangeDisabled);
+    require!(
+        proposal.status == ProposalStatus::Active,
+        GovernanceError::ProposalNotActive
+    );
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        current_time >= proposal.voting_start_time && current_time <= proposal.voting_end_time,
+        GovernanceError::NotInVotingPeriod
+    );
+    require!(
+        vote_record.cast_by == vote_record.voter,
+        GovernanceError::NotDirectVote
+    );
+    Ok(())
+}
+
+/// Sums the unexpired general delegations held by `owner`, passed as remaining accounts
+fn held_delegation_weight(
+    remaining_accounts: &[AccountInfo],
+    dao: Pubkey,
+    owner: Pubkey,
+    timestamp: i64,
+) -> Result<u64> {
+    let mut weight: u64 = 0;
+    let mut counted_delegations: Vec<Pubkey> = Vec::new();
+    for delegation_info in remaining_accounts {
+        require!(
+            !counted_delegations.contains(delegation_info.key),
+            GovernanceError::DuplicateDelegation
+        );
+        counted_delegations.push(delegation_info.key());
+
+        let delegation = Account::<Delegation>::try_from(delegation_info)?;
+        require!(
+            delegation.dao == dao && delegation.delegatee == owner && delegation.scope.is_none(),
+            GovernanceError::InvalidDelegation
+        );
+        if !delegation.is_expired(timestamp) {
+            weight = weight.checked_add(delegation.amount).unwrap();
+        }
+    }
+    Ok(weight)
+}
+
+/// Withdraws `amount` of `token_mint` from a treasury governed by the DAO to `recipient`,
+/// signing as the DAO's PDA. Consumes `[treasury, treasury_token_account, recipient,
+/// recipient_token_account]` from the remaining accounts.
+fn execute_treasury_transfer<'a, 'info: 'a>(
+    remaining_accounts: &mut impl Iterator<Item = &'a AccountInfo<'info>>,
+    dao_config: &Account<'info, DaoConfig>,
+    treasury_program: &Program<'info, TreasuryProgram>,
+    token_program: &Program<'info, Token>,
+    recipient: Pubkey,
+    amount: u64,
+    token_mint: Pubkey,
+) -> Result<()> {
+    let mut next_account = || remaining_accounts
+        .next()
+        .cloned()
+        .ok_or(GovernanceError::MissingActionAccounts);
+    let treasury = next_account()?;
+    let treasury_token_account = next_account()?;
+    let recipient_info = next_account()?;
+    let recipient_token_account = next_account()?;
+
+    require!(
+        recipient_info.key() == recipient,
+        GovernanceError::InvalidActionAccounts
+    );
+    require!(
+        Account::<TokenAccount>::try_from(&treasury_token_account)?.mint == token_mint,
+        GovernanceError::InvalidActionAccounts
+    );
+
+    treasury::cpi::withdraw_funds(
+        CpiContext::new_with_signer(
+            treasury_program.to_account_info(),
+            treasury::cpi::accounts::WithdrawFunds {
+                treasury,
+                governance: dao_config.to_account_info(),
+                recipient: recipient_info,
+                treasury_token_account,
+                recipient_token_account,
+                token_program: token_program.to_account_info(),
+            },
+            &[&[
+                b"dao_config",
+                dao_config.token_mint.as_ref(),
+                dao_config.authority.as_ref(),
+                &[dao_config.bump],
+            ]],
+        ),
+        amount,
+    )
+}
+
+/// Invokes an arbitrary instruction with the DAO's native treasury PDA as signer. Consumes the
+/// target program followed by each of the instruction's accounts from the remaining accounts.
+fn execute_instruction<'a, 'info: 'a>(
+    remaining_accounts: &mut impl Iterator<Item = &'a AccountInfo<'info>>,
+    dao: Pubkey,
+    native_treasury_bump: u8,
+    program_id: Pubkey,
+    accounts: &[AccountMetaData],
+    data: &[u8],
+) -> Result<()> {
+    let mut account_infos = Vec::with_capacity(accounts.len() + 1);
+    for expected in std::iter::once(&program_id).chain(accounts.iter().map(|meta| &meta.pubkey)) {
+        let account_info = remaining_accounts
+            .next()
+            .ok_or(GovernanceError::MissingActionAccounts)?;
+        require!(
+            account_info.key == expected,
+            GovernanceError::InvalidActionAccounts
+        );
+        account_infos.push(account_info.clone());
+    }
+
+    let instruction = Instruction {
+        program_id,
+        accounts: accounts
+            .iter()
+            .map(|meta| AccountMeta {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+    invoke_signed(
+        &instruction,
+        &account_infos,
+        &[&[b"native_treasury", dao.as_ref(), &[native_treasury_bump]]],
+    )?;
+    Ok(())
+}
+
+/// Casts the delegations held by `voter`, directly or through a chain of delegatees, and
+/// returns their combined weight. Delegations are passed as `[delegation, delegator_vote_record]`
+/// pairs, each delegated either to the voter or to the delegator of an earlier pair, up to
+/// `max_delegation_depth` hops. Scoped delegations only apply to proposals whose actions all
+/// fall within their scope, and on such a proposal a general delegation only applies when the
+/// delegator has no live delegation in that scope; its pair is followed by the delegator's
+/// delegation account for the scope, which may be empty.
+///
+/// Each delegation is recorded in the delegator's own vote record so the weight is counted once
+/// per proposal. A delegator who has already voted is skipped and one who votes later overrides
+/// the delegatee. When the weight was cast through a general delegation, or a longer chain in
+/// the same kind of scope, the voter takes it over and the pair is followed by the vote record
+/// of the previous caster.
+fn cast_delegated_votes<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    proposal: &mut Account<'info, Proposal>,
+    delegate_vote_record: &mut Option<Account<'info, VoteRecord>>,
+    max_delegation_depth: u8,
+    delegated_vote: &DelegatedVote<'_, 'info>,
+    timestamp: i64,
+) -> Result<u64> {
+    let DelegatedVote { voter, system_program, vote_option } = *delegated_vote;
+    let proposal_key = proposal.key();
+    let proposal_scope = proposal.action_scope();
+    let mut delegated_weight: u64 = 0;
+    let mut counted_delegations: Vec<Pubkey> = Vec::new();
+    // Owners whose weight this vote carries, with their distance from the voter
+    let mut represented: Vec<(Pubkey, u8)> = vec![(voter.key(), 0)];
+
+    let mut accounts = remaining_accounts.iter();
+    while let Some(delegation_info) = accounts.next() {
+        let delegator_vote_record_info = accounts
+            .next()
+            .ok_or(GovernanceError::InvalidDelegatorVoteRecord)?;
+        require!(
+            !counted_delegations.contains(delegation_info.key),
+            GovernanceError::DuplicateDelegation
+        );
+        counted_delegations.push(delegation_info.key());
+
+        let delegation = Account::<Delegation>::try_from(delegation_info)?;
+        let depth = represented
+            .iter()
+            .find(|(owner, _)| *owner == delegation.delegatee)
+            .map(|(_, depth)| depth + 1)
+            .ok_or(GovernanceError::InvalidDelegation)?;
+        require!(
+            depth <= max_delegation_depth,
+            GovernanceError::DelegationTooDeep
+        );
+        require!(delegation.dao == proposal.dao, GovernanceError::InvalidDelegation);
+        require!(
+            delegation.timestamp < proposal.voting_start_time,
+            GovernanceError::DelegationAfterSnapshot
+        );
+        require!(
+            delegation.scope.is_none() || delegation.scope == proposal_scope,
+            GovernanceError::DelegationScopeMismatch
+        );
+        if let (None, Some(scope)) = (delegation.scope, proposal_scope) {
+            let scoped_delegation_info = accounts
+                .next()
+                .ok_or(GovernanceError::InvalidDelegation)?;
+            let (scoped_delegation, _) = Pubkey::find_program_address(
+                &[
+                    b"delegation",
+                    proposal.dao.as_ref(),
+                    delegation.delegator.as_ref(),
+                    &[delegation_scope_index(Some(scope))],
+                ],
+                &crate::ID,
+            );
+            require!(
+                scoped_delegation_info.key() == scoped_delegation,
+                GovernanceError::InvalidDelegation
+            );
+            if !scoped_delegation_info.data_is_empty()
+                && Account::<Delegation>::try_from(scoped_delegation_info)?.is_live_at(proposal.voting_start_time)
+            {
+                continue;
+            }
+        }
+        let is_scoped_delegation = delegation.scope.is_some();
+        // Like the weight itself, expiry is resolved when voting opens
+        if delegation.amount == 0
+            || delegation.is_expired(proposal.voting_start_time)
+            || represented.iter().any(|(owner, _)| *owner == delegation.delegator)
+        {
+            continue;
+        }
+
+        let (delegator_vote_record, bump) = Pubkey::find_program_address(
+            &[b"vote", proposal_key.as_ref(), delegation.delegator.as_ref()],
+            &crate::ID,
+        );
+        require!(
+            delegator_vote_record_info.key() == delegator_vote_record,
+            GovernanceError::InvalidDelegatorVoteRecord
+        );
+        // Changes to a record the instruction also loaded would be overwritten when it exits
+        require!(
+            delegate_vote_record
+                .as_ref()
+                .map_or(true, |record| record.key() != delegator_vote_record),
+            GovernanceError::InvalidDelegatorVoteRecord
+        );
+
+        if delegator_vote_record_info.data_is_empty() {
+            create_pda_account(
+                delegator_vote_record_info,
+                voter,
+                system_program,
+                VoteRecord::LEN,
+                &[b"vote", proposal_key.as_ref(), delegation.delegator.as_ref(), &[bump]],
+            )?;
+            let vote_record = VoteRecord {
+                proposal: proposal_key,
+                voter: delegation.delegator,
+                vote_option,
+                token_amount: delegation.amount,
+                timestamp,
+                is_relinquished: false,
+                cast_by: voter.key(),
+                delegation_depth: depth,
+                is_scoped_delegation,
+                delegated_weight: 0,
+                is_withdrawn: false,
+                change_count: 0,
+                payer: voter.key(),
+                bump,
+            };
+            vote_record.try_serialize(&mut &mut delegator_vote_record_info.try_borrow_mut_data()?[..])?;
+        } else {
+            // Skip delegators who voted directly, and weight already cast through a scoped
+            // delegation or a chain at least as close
+            let mut vote_record = Account::<VoteRecord>::try_from(delegator_vote_record_info)?;
+            let takes_precedence = (is_scoped_delegation && !vote_record.is_scoped_delegation)
+                || (is_scoped_delegation == vote_record.is_scoped_delegation
+                    && depth < vote_record.delegation_depth);
+            if vote_record.cast_by == vote_record.voter || !takes_precedence {
+                continue;
+            }
+
+            let previous_caster_info = accounts
+                .next()
+                .ok_or(GovernanceError::InvalidDelegateVoteRecord)?;
+            match delegate_vote_record {
+                // Already loaded by the instruction when the voter is overriding the same caster
+                Some(previous_caster) if previous_caster.key() == previous_caster_info.key() => {
+                    previous_caster.release_delegated_weight(proposal, vote_record.token_amount);
+                }
+                _ => {
+                    let mut previous_caster = Account::<VoteRecord>::try_from(previous_caster_info)?;
+                    require!(
+                        previous_caster.proposal == proposal_key && previous_caster.voter == vote_record.cast_by,
+                        GovernanceError::InvalidDelegateVoteRecord
+                    );
+                    previous_caster.release_delegated_weight(proposal, vote_record.token_amount);
+                    previous_caster.exit(&crate::ID)?;
+                }
+            }
+
+            vote_record.vote_option = vote_option;
+            vote_record.token_amount = delegation.amount;
+            vote_record.timestamp = timestamp;
+            vote_record.cast_by = voter.key();
+            vote_record.delegation_depth = depth;
+            vote_record.is_scoped_delegation = is_scoped_delegation;
+            vote_record.exit(&crate::ID)?;
+        }
+
+        represented.push((delegation.delegator, depth));
+        delegated_weight = delegated_weight.checked_add(delegation.amount).unwrap();
+    }
+
+    Ok(delegated_weight)
+}
+
+/// The voter casting delegated weight, who pays for delegators' new vote records, and their choice
+struct DelegatedVote<'a, 'info> {
+    voter: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+    vote_option: VoteOption,
+}
+
+/// Creates a program-owned account at a PDA, topping up any lamports already sent to it
+fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let required_lamports = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if required_lamports > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            required_lamports,
+        )?;
+    }
+    anchor_lang::system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    anchor_lang::system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        &crate::ID,
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeDao<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = DaoConfig::LEN,
+        seeds = [b"dao_config", token_mint.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub token_mint: Account<'info, token::Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"governing_token_vault", dao_config.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = dao_config,
+    )]
+    pub governing_token_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
 
 #[derive(Accounts)]
 pub struct CreateProposal<'info> {
@@ -209,13 +1466,14 @@ pub struct CreateProposal<'info> {
         init,
         payer = creator,
         space = Proposal::LEN,
-        seeds = [b"proposal", creator.key().as_ref()],
+        seeds = [b"proposal", dao_config.key().as_ref(), &dao_config.proposal_count.to_le_bytes()],
         bump
     )]
     pub proposal: Account<'info, Proposal>,
     
     #[account(
-        seeds = [b"dao_config"],
+        mut,
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
         bump = dao_config.bump,
     )]
     pub dao_config: Account<'info, DaoConfig>,
@@ -223,6 +1481,13 @@ pub struct CreateProposal<'info> {
     #[account(mut)]
     pub creator: Signer<'info>,
     
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), creator.key().as_ref()],
+        bump = creator_token_owner_record.bump,
+    )]
+    pub creator_token_owner_record: Account<'info, TokenOwnerRecord>,
+    
     #[account(
         mut,
         constraint = creator_token_account.owner == creator.key(),
@@ -231,120 +1496,806 @@ pub struct CreateProposal<'info> {
     pub creator_token_account: Account<'info, TokenAccount>,
     
     #[account(
-        mut,
-        constraint = dao_token_account.owner == dao_config.key(),
-        constraint = dao_token_account.mint == dao_config.token_mint,
+        init,
+        payer = creator,
+        seeds = [b"proposal_deposit", proposal.key().as_ref()],
+        bump,
+        token::mint = governing_token_mint,
+        token::authority = proposal,
     )]
-    pub dao_token_account: Account<'info, TokenAccount>,
+    pub proposal_deposit: Account<'info, TokenAccount>,
+    
+    #[account(address = dao_config.token_mint)]
+    pub governing_token_mint: Account<'info, token::Mint>,
     
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct CastVote<'info> {
+pub struct UpdateProposal<'info> {
     #[account(
         mut,
-        seeds = [b"proposal", proposal.creator.as_ref()],
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(signatory: Pubkey)]
+pub struct AddSignatory<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
         bump = proposal.bump,
     )]
     pub proposal: Account<'info, Proposal>,
     
     #[account(
         init,
-        payer = voter,
-        space = VoteRecord::LEN,
-        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        payer = creator,
+        space = SignatoryRecord::LEN,
+        seeds = [b"signatory", proposal.key().as_ref(), signatory.as_ref()],
         bump
     )]
-    pub vote_record: Account<'info, VoteRecord>,
+    pub signatory_record: Account<'info, SignatoryRecord>,
     
     #[account(mut)]
-    pub voter: Signer<'info>,
+    pub creator: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct DelegateVotes<'info> {
+#[instruction(option_index: u8, action: ProposedAction)]
+pub struct InsertTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
     #[account(
         init,
-        payer = delegator,
-        space = Delegation::LEN,
-        seeds = [b"delegation", delegator.key().as_ref(), delegatee.key().as_ref()],
+        payer = creator,
+        space = ProposalTransaction::space(&action),
+        seeds = [
+            b"proposal_transaction",
+            proposal.key().as_ref(),
+            &[option_index],
+            &proposal.next_transaction_index(option_index).to_le_bytes(),
+        ],
         bump
     )]
-    pub delegation: Account<'info, Delegation>,
+    pub proposal_transaction: Account<'info, ProposalTransaction>,
     
     #[account(mut)]
-    pub delegator: Signer<'info>,
-    
-    /// CHECK: This is just a pubkey
-    pub delegatee: AccountInfo<'info>,
+    pub creator: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RevokeDelegation<'info> {
+pub struct RemoveTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        close = creator,
+        seeds = [
+            b"proposal_transaction",
+            proposal.key().as_ref(),
+            &[proposal_transaction.option_index],
+            &proposal_transaction.index.to_le_bytes(),
+        ],
+        bump = proposal_transaction.bump,
+    )]
+    pub proposal_transaction: Account<'info, ProposalTransaction>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SignOffProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"signatory", proposal.key().as_ref(), signatory.key().as_ref()],
+        bump = signatory_record.bump,
+    )]
+    pub signatory_record: Option<Account<'info, SignatoryRecord>>,
+    
+    pub signatory: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = VoteRecord::LEN,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    /// Vote record of the delegatee that cast the voter's delegated weight, when overriding
+    #[account(
+        mut,
+        constraint = delegate_vote_record.proposal == proposal.key(),
+    )]
+    pub delegate_vote_record: Option<Account<'info, VoteRecord>>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), voter.key().as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ChangeVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RelinquishVote<'info> {
+    #[account(
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        seeds = [b"vote", proposal.key().as_ref(), vote_record.voter.as_ref()],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", proposal.dao.as_ref(), vote_record.voter.as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    /// Only needed to relinquish before the proposal is settled
+    pub voter: Option<Signer<'info>>,
+    
+    /// CHECK: Receives the vote record rent
+    #[account(
+        mut,
+        address = vote_record.payer,
+    )]
+    pub payer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositGoverningTokens<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = TokenOwnerRecord::LEN,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"governing_token_vault", dao_config.key().as_ref()],
+        bump,
+    )]
+    pub governing_token_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == dao_config.token_mint,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawGoverningTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"governing_token_vault", dao_config.key().as_ref()],
+        bump,
+    )]
+    pub governing_token_vault: Account<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == dao_config.token_mint,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateLock<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = VoteLock::LEN,
+        seeds = [b"vote_lock", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub vote_lock: Account<'info, VoteLock>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = TokenOwnerRecord::LEN,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"governing_token_vault", dao_config.key().as_ref()],
+        bump,
+    )]
+    pub governing_token_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == dao_config.token_mint,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncreaseLockAmount<'info> {
+    #[account(
+        mut,
+        seeds = [b"vote_lock", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump = vote_lock.bump,
+    )]
+    pub vote_lock: Account<'info, VoteLock>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"governing_token_vault", dao_config.key().as_ref()],
+        bump,
+    )]
+    pub governing_token_vault: Account<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == dao_config.token_mint,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"vote_lock", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump = vote_lock.bump,
+    )]
+    pub vote_lock: Account<'info, VoteLock>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawExpired<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"vote_lock", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump = vote_lock.bump,
+    )]
+    pub vote_lock: Account<'info, VoteLock>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), owner.key().as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"governing_token_vault", dao_config.key().as_ref()],
+        bump,
+    )]
+    pub governing_token_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == dao_config.token_mint,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, scope: Option<ActionKind>)]
+pub struct DelegateVotes<'info> {
+    #[account(
+        init,
+        payer = delegator,
+        space = Delegation::LEN,
+        seeds = [b"delegation", dao_config.key().as_ref(), delegator.key().as_ref(), &[delegation_scope_index(scope)]],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", dao_config.key().as_ref(), delegator.key().as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    
+    /// CHECK: This is just a pubkey
+    pub delegatee: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"delegation", delegation.dao.as_ref(), delegator.key().as_ref(), &[delegation_scope_index(delegation.scope)]],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", delegation.dao.as_ref(), delegator.key().as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDelegation<'info> {
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"delegation", delegation.dao.as_ref(), delegation.delegator.as_ref(), &[delegation_scope_index(delegation.scope)]],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", delegation.dao.as_ref(), delegation.delegator.as_ref()],
+        bump = token_owner_record.bump,
+    )]
+    pub token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    /// CHECK: Receives the delegation rent
+    #[account(
+        mut,
+        address = delegation.delegator,
+    )]
+    pub delegator: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProposalStatus<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", proposal.dao.as_ref(), proposal.creator.as_ref()],
+        bump = creator_token_owner_record.bump,
+    )]
+    pub creator_token_owner_record: Account<'info, TokenOwnerRecord>,
+    
+    pub oracle_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    #[account(address = dao_config.token_mint)]
+    pub governing_token_mint: Account<'info, token::Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", proposal.dao.as_ref(), proposal.creator.as_ref()],
+        bump = creator_token_owner_record.bump,
+    )]
+    pub creator_token_owner_record: Account<'info, TokenOwnerRecord>,
+}
+
+#[derive(Accounts)]
+pub struct QueueProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    pub creator: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_owner_record", proposal.dao.as_ref(), proposal.creator.as_ref()],
+        bump = creator_token_owner_record.bump,
+    )]
+    pub creator_token_owner_record: Account<'info, TokenOwnerRecord>,
+}
+
+#[derive(Accounts)]
+pub struct VetoProposal<'info> {
     #[account(
         mut,
-        seeds = [b"delegation", delegator.key().as_ref(), delegation.delegatee.as_ref()],
-        bump = delegation.bump,
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
     )]
-    pub delegation: Account<'info, Delegation>,
+    pub proposal: Account<'info, Proposal>,
     
-    #[account(mut)]
-    pub delegator: Signer<'info>,
+    #[account(
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
+        bump = dao_config.bump,
+    )]
+    pub dao_config: Account<'info, DaoConfig>,
+    
+    pub guardian: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateProposalStatus<'info> {
+pub struct ReleaseDeposit<'info> {
     #[account(
         mut,
-        seeds = [b"proposal", proposal.creator.as_ref()],
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
         bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
     )]
     pub proposal: Account<'info, Proposal>,
     
     #[account(
-        seeds = [b"dao_config"],
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
         bump = dao_config.bump,
     )]
     pub dao_config: Account<'info, DaoConfig>,
     
-    pub oracle_authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"proposal_deposit", proposal.key().as_ref()],
+        bump,
+    )]
+    pub proposal_deposit: Account<'info, TokenAccount>,
+    
+    /// CHECK: Receives the escrow rent
+    #[account(
+        mut,
+        address = proposal.creator,
+    )]
+    pub creator: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == proposal.creator,
+        constraint = creator_token_account.mint == dao_config.token_mint,
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+    
+    /// Only needed when the deposit is slashed
+    #[account(
+        constraint = treasury.governance == dao_config.key(),
+    )]
+    pub treasury: Option<Account<'info, treasury::Treasury>>,
+    
+    #[account(
+        mut,
+        constraint = treasury.as_ref().map_or(false, |treasury| treasury_token_account.owner == treasury.key()),
+        constraint = treasury_token_account.mint == dao_config.token_mint,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct ExecuteProposal<'info> {
+pub struct ExecuteTransaction<'info> {
     #[account(
         mut,
-        seeds = [b"proposal", proposal.creator.as_ref()],
+        seeds = [b"proposal", proposal.dao.as_ref(), &proposal.id.to_le_bytes()],
         bump = proposal.bump,
+        constraint = proposal.dao == dao_config.key(),
     )]
     pub proposal: Account<'info, Proposal>,
     
     #[account(
         mut,
-        seeds = [b"dao_config"],
+        seeds = [
+            b"proposal_transaction",
+            proposal.key().as_ref(),
+            &[proposal_transaction.option_index],
+            &proposal_transaction.index.to_le_bytes(),
+        ],
+        bump = proposal_transaction.bump,
+    )]
+    pub proposal_transaction: Account<'info, ProposalTransaction>,
+    
+    #[account(
+        mut,
+        seeds = [b"dao_config", dao_config.token_mint.as_ref(), dao_config.authority.as_ref()],
         bump = dao_config.bump,
     )]
     pub dao_config: Account<'info, DaoConfig>,
     
     pub executor: Signer<'info>,
+    
+    /// Signs instructions proposed by the DAO
+    #[account(
+        seeds = [b"native_treasury", dao_config.key().as_ref()],
+        bump,
+    )]
+    pub native_treasury: SystemAccount<'info>,
+    
+    pub treasury_program: Program<'info, TreasuryProgram>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[account]
 pub struct DaoConfig {
     pub authority: Pubkey,
     pub token_mint: Pubkey,
-    pub proposal_fee: u64,
+    pub proposal_deposit: u64,
     pub voting_period: i64,
     pub quorum_percentage: u8,
     pub threshold_percentage: u8,
     pub require_poh: bool,
     pub oracle_authority: Pubkey,
+    pub proposal_count: u64,
+    pub max_lock_duration: i64,
+    pub max_delegation_depth: u8,
+    pub voting_delay: i64,
+    pub timelock_delay: i64,
+    pub grace_period: i64,
+    pub guardian: Pubkey,
+    pub refund_deposit_on_cancel: bool,
+    pub deposit_participation_percentage: u8,
+    pub allow_vote_change: bool,
+    pub min_tokens_to_create_proposal: u64,
+    pub max_active_proposals_per_creator: u8,
+    pub max_voting_period: i64,
+    pub latest_voting_start_time: i64,
     pub bump: u8,
 }
 
@@ -352,47 +2303,226 @@ impl DaoConfig {
     pub const LEN: usize = 8 + // discriminator
         32 + // authority
         32 + // token_mint
-        8 + // proposal_fee
+        8 + // proposal_deposit
         8 + // voting_period
         1 + // quorum_percentage
         1 + // threshold_percentage
         1 + // require_poh
         32 + // oracle_authority
+        8 + // proposal_count
+        8 + // max_lock_duration
+        1 + // max_delegation_depth
+        8 + // voting_delay
+        8 + // timelock_delay
+        8 + // grace_period
+        32 + // guardian
+        1 + // refund_deposit_on_cancel
+        1 + // deposit_participation_percentage
+        1 + // allow_vote_change
+        8 + // min_tokens_to_create_proposal
+        1 + // max_active_proposals_per_creator
+        8 + // max_voting_period
+        8 + // latest_voting_start_time
         1; // bump
 }
 
 #[account]
 pub struct Proposal {
     pub creator: Pubkey,
+    pub dao: Pubkey,
+    pub id: u64,
     pub title: String,
     pub description: String,
     pub snapshot_id: String,
-    pub actions: Vec<ProposedAction>,
+    pub transaction_counts: [u16; MAX_OPTIONS],
+    pub executed_transaction_count: u16,
+    pub action_kind_counts: [u16; ActionKind::ALL.len()],
+    pub signatories_count: u8,
+    pub signatories_signed_off_count: u8,
     pub creation_time: i64,
     pub voting_start_time: i64,
     pub voting_end_time: i64,
+    pub eta: i64,
     pub status: ProposalStatus,
     pub for_votes: u64,
     pub against_votes: u64,
     pub abstain_votes: u64,
+    pub vote_type: VoteType,
+    pub options: Vec<ProposalOption>,
+    pub choice_votes: u64,
+    pub winning_option: u8,
+    pub deposit_amount: u64,
+    pub deposit_refundable: bool,
     pub bump: u8,
 }
 
 impl Proposal {
     pub const LEN: usize = 8 + // discriminator
         32 + // creator
+        32 + // dao
+        8 + // id
         100 + // title (max length)
         500 + // description (max length)
         100 + // snapshot_id (max length)
-        1000 + // actions (max size)
+        2 * MAX_OPTIONS + // transaction_counts
+        2 + // executed_transaction_count
+        2 * ActionKind::ALL.len() + // action_kind_counts
+        1 + // signatories_count
+        1 + // signatories_signed_off_count
         8 + // creation_time
         8 + // voting_start_time
         8 + // voting_end_time
+        8 + // eta
         1 + // status
         8 + // for_votes
         8 + // against_votes
         8 + // abstain_votes
+        1 + // vote_type
+        4 + MAX_OPTIONS * ProposalOption::LEN + // options
+        8 + // choice_votes
+        1 + // winning_option
+        8 + // deposit_amount
+        1 + // deposit_refundable
         1; // bump
+
+    /// Whether the proposal is a draft no signatory has signed off on yet
+    pub fn is_editable(&self) -> bool {
+        self.status == ProposalStatus::Draft && self.signatories_signed_off_count == 0
+    }
+
+    /// Whether the proposal is waiting for or in its voting period
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, ProposalStatus::Pending | ProposalStatus::Active)
+    }
+
+    /// Whether the outcome is final and no longer depends on individual vote records
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self.status,
+            ProposalStatus::Defeated
+                | ProposalStatus::Executed
+                | ProposalStatus::Expired
+                | ProposalStatus::Canceled
+                | ProposalStatus::Vetoed
+        )
+    }
+
+    /// Last moment a queued proposal can be executed
+    pub fn grace_period_end(&self, grace_period: i64) -> i64 {
+        self.eta.checked_add(grace_period).unwrap()
+    }
+
+    /// Outcome and winning option once voting has ended. Quorum counts every vote cast,
+    /// including abstentions, against the maximum voting weight of the supply. The threshold
+    /// applies to for vs. against votes, or to a multiple-choice option's share of the votes;
+    /// a tie between leading options defeats the proposal.
+    pub fn tally(&self, supply: u64, quorum_percentage: u8, threshold_percentage: u8) -> (ProposalStatus, u8) {
+        let quorum_reached = self.participation_reached(supply, quorum_percentage);
+        let (winning_option, winning_votes, decided_votes) = match self.vote_type {
+            VoteType::YesNo => (0, self.for_votes, self.for_votes as u128 + self.against_votes as u128),
+            VoteType::SingleChoice | VoteType::Approval => {
+                let winning_votes = self.options.iter().map(|option| option.vote_weight).max().unwrap_or(0);
+                let mut leaders = self.options
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, option)| option.vote_weight == winning_votes);
+                let (winning_option, _) = leaders.next().unwrap();
+                if leaders.next().is_some() {
+                    return (ProposalStatus::Defeated, 0);
+                }
+                (winning_option as u8, winning_votes, self.choice_votes as u128)
+            }
+        };
+        let threshold_reached = winning_votes > 0
+            && winning_votes as u128 * 100 >= decided_votes * threshold_percentage as u128;
+
+        if quorum_reached && threshold_reached {
+            (ProposalStatus::Succeeded, winning_option)
+        } else {
+            (ProposalStatus::Defeated, 0)
+        }
+    }
+
+    /// Whether every vote cast, including abstentions, makes up `percentage` of the maximum
+    /// voting weight, which counts the whole supply as locked for the maximum duration
+    pub fn participation_reached(&self, supply: u64, percentage: u8) -> bool {
+        let cast_votes = self.for_votes as u128
+            + self.against_votes as u128
+            + self.abstain_votes as u128
+            + self.choice_votes as u128;
+        let max_voter_weight = supply as u128 * MAX_LOCK_MULTIPLIER as u128;
+        cast_votes * 100 >= max_voter_weight * percentage as u128
+    }
+
+    /// Options that can carry actions; a yes/no proposal has a single one
+    pub fn option_count(&self) -> usize {
+        match self.vote_type {
+            VoteType::YesNo => 1,
+            VoteType::SingleChoice | VoteType::Approval => self.options.len(),
+        }
+    }
+
+    /// Index the next transaction of `option_index` is stored at
+    pub fn next_transaction_index(&self, option_index: u8) -> u16 {
+        self.transaction_counts.get(option_index as usize).copied().unwrap_or_default()
+    }
+
+    /// Whether `vote_option` fits the proposal's vote type and options
+    pub fn is_valid_vote(&self, vote_option: VoteOption) -> bool {
+        match (self.vote_type, vote_option) {
+            (VoteType::YesNo, VoteOption::For | VoteOption::Against | VoteOption::Abstain) => true,
+            (VoteType::SingleChoice | VoteType::Approval, VoteOption::Choices { bitmap }) => {
+                let selected = bitmap.count_ones();
+                let within_options = (bitmap >> self.options.len()) == 0;
+                within_options
+                    && selected > 0
+                    && (self.vote_type == VoteType::Approval || selected == 1)
+            }
+            _ => false,
+        }
+    }
+
+    /// Action kind shared by every action of the proposal, which selects scoped delegations
+    pub fn action_scope(&self) -> Option<ActionKind> {
+        let mut kinds = ActionKind::ALL
+            .into_iter()
+            .filter(|kind| self.action_kind_counts[*kind as usize] > 0);
+        let kind = kinds.next()?;
+        kinds.next().is_none().then_some(kind)
+    }
+
+    /// Approval votes give their full weight to every selected option
+    pub fn add_votes(&mut self, vote_option: VoteOption, amount: u64) {
+        match vote_option {
+            VoteOption::For => self.for_votes = self.for_votes.checked_add(amount).unwrap(),
+            VoteOption::Against => self.against_votes = self.against_votes.checked_add(amount).unwrap(),
+            VoteOption::Abstain => self.abstain_votes = self.abstain_votes.checked_add(amount).unwrap(),
+            VoteOption::Choices { bitmap } => {
+                for (index, option) in self.options.iter_mut().enumerate() {
+                    if bitmap & (1 << index) != 0 {
+                        option.vote_weight = option.vote_weight.checked_add(amount).unwrap();
+                    }
+                }
+                self.choice_votes = self.choice_votes.checked_add(amount).unwrap();
+            }
+        }
+    }
+
+    pub fn remove_votes(&mut self, vote_option: VoteOption, amount: u64) {
+        match vote_option {
+            VoteOption::For => self.for_votes = self.for_votes.checked_sub(amount).unwrap(),
+            VoteOption::Against => self.against_votes = self.against_votes.checked_sub(amount).unwrap(),
+            VoteOption::Abstain => self.abstain_votes = self.abstain_votes.checked_sub(amount).unwrap(),
+            VoteOption::Choices { bitmap } => {
+                for (index, option) in self.options.iter_mut().enumerate() {
+                    if bitmap & (1 << index) != 0 {
+                        option.vote_weight = option.vote_weight.checked_sub(amount).unwrap();
+                    }
+                }
+                self.choice_votes = self.choice_votes.checked_sub(amount).unwrap();
+            }
+        }
+    }
 }
 
 #[account]
@@ -402,6 +2532,14 @@ pub struct VoteRecord {
     pub vote_option: VoteOption,
     pub token_amount: u64,
     pub timestamp: i64,
+    pub is_relinquished: bool,
+    pub cast_by: Pubkey,
+    pub delegation_depth: u8,
+    pub is_scoped_delegation: bool,
+    pub delegated_weight: u64,
+    pub is_withdrawn: bool,
+    pub change_count: u8,
+    pub payer: Pubkey,
     pub bump: u8,
 }
 
@@ -409,28 +2547,255 @@ impl VoteRecord {
     pub const LEN: usize = 8 + // discriminator
         32 + // proposal
         32 + // voter
-        1 + // vote_option
+        3 + // vote_option
         8 + // token_amount
         8 + // timestamp
+        1 + // is_relinquished
+        32 + // cast_by
+        1 + // delegation_depth
+        1 + // is_scoped_delegation
+        8 + // delegated_weight
+        1 + // is_withdrawn
+        1 + // change_count
+        32 + // payer
+        1; // bump
+
+    /// Own and delegated weight of a direct vote
+    pub fn cast_weight(&self) -> u64 {
+        self.token_amount.checked_add(self.delegated_weight).unwrap()
+    }
+
+    /// Takes back `amount` of the delegated weight this caster cast. Delegated weight follows the
+    /// caster's current choice, so it is removed from that option unless the vote was withdrawn.
+    pub fn release_delegated_weight(&mut self, proposal: &mut Proposal, amount: u64) {
+        self.delegated_weight = self.delegated_weight.checked_sub(amount).unwrap();
+        if !self.is_withdrawn {
+            proposal.remove_votes(self.vote_option, amount);
+        }
+    }
+}
+
+#[account]
+pub struct ProposalTransaction {
+    pub proposal: Pubkey,
+    pub option_index: u8,
+    pub index: u16,
+    pub action: ProposedAction,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl ProposalTransaction {
+    pub fn space(action: &ProposedAction) -> usize {
+        8 + // discriminator
+        32 + // proposal
+        1 + // option_index
+        2 + // index
+        action.try_to_vec().unwrap().len() + // action
+        1 + // executed
+        1 // bump
+    }
+}
+
+#[account]
+pub struct SignatoryRecord {
+    pub proposal: Pubkey,
+    pub signatory: Pubkey,
+    pub signed_off: bool,
+    pub bump: u8,
+}
+
+impl SignatoryRecord {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // proposal
+        32 + // signatory
+        1 + // signed_off
+        1; // bump
+}
+
+#[account]
+pub struct TokenOwnerRecord {
+    pub owner: Pubkey,
+    pub governing_token_deposit_amount: u64,
+    pub delegated_amounts: [u64; MAX_DELEGATION_SCOPES],
+    pub outstanding_vote_count: u32,
+    pub active_proposal_count: u8,
+    pub checkpoints: Vec<WeightCheckpoint>,
+    pub bump: u8,
+}
+
+impl TokenOwnerRecord {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // owner
+        8 + // governing_token_deposit_amount
+        8 * MAX_DELEGATION_SCOPES + // delegated_amounts
+        4 + // outstanding_vote_count
+        1 + // active_proposal_count
+        4 + MAX_CHECKPOINTS * WeightCheckpoint::LEN + // checkpoints
+        1; // bump
+
+    /// Deposit that is not backing a delegation in any scope
+    pub fn undelegated_deposit_amount(&self) -> u64 {
+        let delegated_amount = self.delegated_amounts.iter().copied().max().unwrap_or(0);
+        self.governing_token_deposit_amount.saturating_sub(delegated_amount)
+    }
+
+    /// Current undelegated deposit plus lock power at `timestamp`, so deposit delegated away
+    /// only counts toward the delegatee
+    pub fn undelegated_weight(&self, timestamp: i64, max_lock_duration: i64) -> u64 {
+        let lock_weight = self.checkpoints
+            .last()
+            .map(|checkpoint| {
+                lock_voting_power(checkpoint.lock_amount, checkpoint.lock_end, timestamp, max_lock_duration)
+            })
+            .unwrap_or(0);
+        self.undelegated_deposit_amount().checked_add(lock_weight).unwrap()
+    }
+
+    /// Appends a checkpoint at `timestamp` derived from the latest one, or updates it in place
+    /// when the latest checkpoint was taken at the same time. Once full, a checkpoint no
+    /// proposal can still snapshot is dropped to make room.
+    pub fn record_checkpoint(
+        &mut self,
+        timestamp: i64,
+        dao_config: &DaoConfig,
+        update: impl FnOnce(&mut WeightCheckpoint),
+    ) -> Result<()> {
+        let mut checkpoint = self.checkpoints.last().cloned().unwrap_or_default();
+        checkpoint.timestamp = timestamp;
+        update(&mut checkpoint);
+
+        match self.checkpoints.last_mut() {
+            Some(latest) if latest.timestamp == timestamp => *latest = checkpoint,
+            _ => {
+                if self.checkpoints.len() == MAX_CHECKPOINTS {
+                    let index = self
+                        .unneeded_checkpoint(timestamp, dao_config)
+                        .ok_or(GovernanceError::CheckpointStillNeeded)?;
+                    self.checkpoints.remove(index);
+                }
+                self.checkpoints.push(checkpoint);
+            }
+        }
+        Ok(())
+    }
+
+    /// A checkpoint is the snapshot for voting starts after it and up to the next checkpoint.
+    /// Proposals open for voting started within the widest voting period ever configured, and
+    /// proposals signed off from now on start at `timestamp` or later, so the oldest checkpoint
+    /// is unneeded once the next one predates that window, and the one before the latest is
+    /// unneeded when no proposal has a voting start after it.
+    fn unneeded_checkpoint(&self, timestamp: i64, dao_config: &DaoConfig) -> Option<usize> {
+        let oldest_open_voting_start = timestamp.saturating_sub(dao_config.max_voting_period);
+        let before_latest = self.checkpoints.len().checked_sub(2)?;
+        if self.checkpoints[1].timestamp < oldest_open_voting_start {
+            Some(0)
+        } else if self.checkpoints[before_latest].timestamp >= dao_config.latest_voting_start_time {
+            Some(before_latest)
+        } else {
+            None
+        }
+    }
+
+    pub fn record_lock_checkpoint(&mut self, timestamp: i64, dao_config: &DaoConfig, vote_lock: &VoteLock) -> Result<()> {
+        self.record_checkpoint(timestamp, dao_config, |checkpoint| {
+            checkpoint.lock_amount = vote_lock.amount;
+            checkpoint.lock_end = vote_lock.lock_end;
+        })
+    }
+
+    /// Own voting weight from checkpoints strictly before `voting_start_time`, with lock power
+    /// evaluated at that time, so weight acquired once voting opens never counts. Delegated
+    /// deposit is included since a direct vote overrides the owner's delegations.
+    pub fn voter_weight_at(&self, voting_start_time: i64, max_lock_duration: i64) -> u64 {
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.timestamp < voting_start_time)
+            .map(|checkpoint| {
+                let lock_weight = lock_voting_power(
+                    checkpoint.lock_amount,
+                    checkpoint.lock_end,
+                    voting_start_time,
+                    max_lock_duration,
+                );
+                checkpoint.deposit_amount.checked_add(lock_weight).unwrap()
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[account]
+pub struct VoteLock {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub lock_end: i64,
+    pub bump: u8,
+}
+
+impl VoteLock {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // owner
+        8 + // amount
+        8 + // lock_end
         1; // bump
 }
 
+/// Voting power of `amount` locked until `lock_end`, decaying linearly to zero at unlock
+pub fn lock_voting_power(amount: u64, lock_end: i64, timestamp: i64, max_lock_duration: i64) -> u64 {
+    if max_lock_duration <= 0 {
+        return 0;
+    }
+    let remaining = lock_end.saturating_sub(timestamp).clamp(0, max_lock_duration);
+    (amount as u128 * MAX_LOCK_MULTIPLIER as u128 * remaining as u128 / max_lock_duration as u128) as u64
+}
+
 #[account]
 pub struct Delegation {
+    pub dao: Pubkey,
     pub delegator: Pubkey,
     pub delegatee: Pubkey,
     pub amount: u64,
+    pub scope: Option<ActionKind>,
+    pub expires_at: Option<i64>,
     pub timestamp: i64,
     pub bump: u8,
 }
 
 impl Delegation {
     pub const LEN: usize = 8 + // discriminator
+        32 + // dao
         32 + // delegator
         32 + // delegatee
         8 + // amount
+        2 + // scope
+        9 + // expires_at
         8 + // timestamp
         1; // bump
+
+    /// Whether the delegation counts on a proposal whose voting starts at `voting_start_time`
+    pub fn is_live_at(&self, voting_start_time: i64) -> bool {
+        self.amount > 0 && self.timestamp < voting_start_time && !self.is_expired(voting_start_time)
+    }
+
+    pub fn is_expired(&self, timestamp: i64) -> bool {
+        self.expires_at.map_or(false, |expires_at| timestamp >= expires_at)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct WeightCheckpoint {
+    pub timestamp: i64,
+    pub deposit_amount: u64,
+    pub lock_amount: u64,
+    pub lock_end: i64,
+}
+
+impl WeightCheckpoint {
+    pub const LEN: usize = 8 + // timestamp
+        8 + // deposit_amount
+        8 + // lock_amount
+        8; // lock_end
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
@@ -440,6 +2805,11 @@ pub enum ProposalStatus {
     Succeeded,
     Defeated,
     Executed,
+    Queued,
+    Expired,
+    Canceled,
+    Vetoed,
+    Draft,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
@@ -447,6 +2817,26 @@ pub enum VoteOption {
     For,
     Against,
     Abstain,
+    /// Options selected on a multiple-choice proposal, one bit per option index
+    Choices { bitmap: u16 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VoteType {
+    YesNo,
+    SingleChoice,
+    Approval,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProposalOption {
+    pub label: String,
+    pub vote_weight: u64,
+}
+
+impl ProposalOption {
+    pub const LEN: usize = 4 + MAX_OPTION_LABEL_LEN + // label
+        8; // vote_weight
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -459,6 +2849,50 @@ pub enum ProposedAction {
     UpdateConfig {
         new_config: DaoConfigUpdate,
     },
+    SetGuardian {
+        guardian: Pubkey,
+    },
+    Instruction {
+        program_id: Pubkey,
+        accounts: Vec<AccountMetaData>,
+        data: Vec<u8>,
+    },
+}
+
+impl ProposedAction {
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            ProposedAction::TreasuryTransfer { .. } => ActionKind::Treasury,
+            ProposedAction::UpdateConfig { .. } | ProposedAction::SetGuardian { .. } => ActionKind::Config,
+            ProposedAction::Instruction { .. } => ActionKind::Instruction,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Treasury,
+    Config,
+    Instruction,
+}
+
+impl ActionKind {
+    pub const ALL: [ActionKind; 3] = [ActionKind::Treasury, ActionKind::Config, ActionKind::Instruction];
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AccountMetaData {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Seed of a delegation scope and its slot in `TokenOwnerRecord::delegated_amounts`
+pub fn delegation_scope_index(scope: Option<ActionKind>) -> u8 {
+    match scope {
+        None => 0,
+        Some(kind) => kind as u8 + 1,
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -470,11 +2904,22 @@ pub struct DaoConfigUpdate {
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct DaoConfig {
-    pub proposal_fee: u64,
+    pub proposal_deposit: u64,
     pub voting_period: i64,
     pub quorum_percentage: u8,
     pub threshold_percentage: u8,
     pub require_poh: bool,
+    pub max_lock_duration: i64,
+    pub max_delegation_depth: u8,
+    pub voting_delay: i64,
+    pub timelock_delay: i64,
+    pub grace_period: i64,
+    pub guardian: Pubkey,
+    pub refund_deposit_on_cancel: bool,
+    pub deposit_participation_percentage: u8,
+    pub allow_vote_change: bool,
+    pub min_tokens_to_create_proposal: u64,
+    pub max_active_proposals_per_creator: u8,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -483,6 +2928,33 @@ pub enum SnapshotOutcome {
     Failed,
 }
 
+#[event]
+pub struct ProposalCreated {
+    pub dao: Pubkey,
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub creator: Pubkey,
+}
+
+#[event]
+pub struct VoteChanged {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub previous_option: VoteOption,
+    pub vote_option: VoteOption,
+    pub weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteWithdrawn {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub vote_option: VoteOption,
+    pub weight: u64,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum GovernanceError {
     #[msg("Proposal is not active")]
@@ -493,4 +2965,381 @@ pub enum GovernanceError {
     NotInVotingPeriod,
     #[msg("Unauthorized")]
     Unauthorized,
-} 
\ No newline at end of file
+    #[msg("Voter has no voting weight")]
+    NoVotingWeight,
+    #[msg("Delegation is not held by the voter")]
+    InvalidDelegation,
+    #[msg("Delegation was passed more than once")]
+    DuplicateDelegation,
+    #[msg("Proposal is still active")]
+    ProposalStillActive,
+    #[msg("Vote has already been relinquished")]
+    VoteAlreadyRelinquished,
+    #[msg("Owner has votes on proposals that are still active")]
+    OutstandingVotes,
+    #[msg("Insufficient deposited governing tokens")]
+    InsufficientDeposit,
+    #[msg("Lock amount must be greater than zero")]
+    InvalidLockAmount,
+    #[msg("Invalid lock duration")]
+    InvalidLockDuration,
+    #[msg("Lock has expired")]
+    LockExpired,
+    #[msg("Lock has not expired yet")]
+    LockNotExpired,
+    #[msg("Delegation was created after voting opened")]
+    DelegationAfterSnapshot,
+    #[msg("Weight history is full and still needed by proposals open for voting")]
+    CheckpointStillNeeded,
+    #[msg("Voter has already voted on this proposal")]
+    AlreadyVoted,
+    #[msg("Vote record of the overridden delegatee is missing or invalid")]
+    InvalidDelegateVoteRecord,
+    #[msg("Vote record of the delegator is invalid")]
+    InvalidDelegatorVoteRecord,
+    #[msg("Delegation chain is missing or invalid")]
+    InvalidDelegationChain,
+    #[msg("Delegation would create a cycle")]
+    DelegationCycle,
+    #[msg("Delegation chain exceeds the maximum depth")]
+    DelegationTooDeep,
+    #[msg("Delegation scope does not match the proposal's actions")]
+    DelegationScopeMismatch,
+    #[msg("Delegation expiry must be in the future")]
+    InvalidDelegationExpiry,
+    #[msg("Delegation has not expired")]
+    DelegationNotExpired,
+    #[msg("Voting period has not ended")]
+    VotingNotEnded,
+    #[msg("Proposal is not queued")]
+    ProposalNotQueued,
+    #[msg("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Proposal has expired")]
+    ProposalExpired,
+    #[msg("Proposal grace period has not ended")]
+    ProposalNotExpired,
+    #[msg("Accounts required by a proposed action are missing")]
+    MissingActionAccounts,
+    #[msg("Accounts passed for a proposed action are invalid")]
+    InvalidActionAccounts,
+    #[msg("Proposal is not a draft")]
+    ProposalNotDraft,
+    #[msg("Only the last transaction can be removed")]
+    NotLastTransaction,
+    #[msg("Proposal can no longer be edited")]
+    ProposalNotEditable,
+    #[msg("Signatory has already signed off")]
+    AlreadySignedOff,
+    #[msg("Creator does not hold enough voting weight to create a proposal")]
+    InsufficientWeightToCreateProposal,
+    #[msg("Creator has too many active proposals")]
+    TooManyActiveProposals,
+    #[msg("Proposal outcome is not final")]
+    ProposalNotSettled,
+    #[msg("A treasury token account is required to slash the deposit")]
+    MissingTreasuryTokenAccount,
+    #[msg("Votes cannot be changed in this DAO")]
+    VoteChangeDisabled,
+    #[msg("Only direct votes can be changed")]
+    NotDirectVote,
+    #[msg("Vote has already been withdrawn")]
+    VoteAlreadyWithdrawn,
+    #[msg("Proposal options do not match its vote type")]
+    InvalidOptions,
+    #[msg("Proposal has no option at this index")]
+    InvalidOptionIndex,
+    #[msg("Vote does not match the proposal's options")]
+    InvalidVoteOption,
+    #[msg("Instruction is not supported for this vote type")]
+    InvalidVoteType,
+    #[msg("Transaction does not belong to the winning option")]
+    OptionNotWinning,
+    #[msg("Action has already been executed")]
+    ActionAlreadyExecuted,
+    #[msg("Previous actions must be executed first")]
+    ActionOutOfOrder,
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposal(vote_type: VoteType, option_count: usize) -> Proposal {
+        Proposal {
+            creator: Pubkey::default(),
+            dao: Pubkey::default(),
+            id: 0,
+            title: String::new(),
+            description: String::new(),
+            snapshot_id: String::new(),
+            transaction_counts: [0; MAX_OPTIONS],
+            executed_transaction_count: 0,
+            action_kind_counts: [0; ActionKind::ALL.len()],
+            signatories_count: 0,
+            signatories_signed_off_count: 0,
+            creation_time: 0,
+            voting_start_time: 0,
+            voting_end_time: 0,
+            eta: 0,
+            status: ProposalStatus::Active,
+            for_votes: 0,
+            against_votes: 0,
+            abstain_votes: 0,
+            vote_type,
+            options: (0..option_count)
+                .map(|index| ProposalOption { label: format!("Option {}", index), vote_weight: 0 })
+                .collect(),
+            choice_votes: 0,
+            winning_option: 0,
+            deposit_amount: 0,
+            deposit_refundable: false,
+            bump: 0,
+        }
+    }
+
+    fn dao_config(max_voting_period: i64, latest_voting_start_time: i64) -> DaoConfig {
+        DaoConfig {
+            authority: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            proposal_deposit: 0,
+            voting_period: max_voting_period,
+            quorum_percentage: 0,
+            threshold_percentage: 0,
+            require_poh: false,
+            oracle_authority: Pubkey::default(),
+            proposal_count: 0,
+            max_lock_duration: 0,
+            max_delegation_depth: 0,
+            voting_delay: 0,
+            timelock_delay: 0,
+            grace_period: 0,
+            guardian: Pubkey::default(),
+            refund_deposit_on_cancel: false,
+            deposit_participation_percentage: 0,
+            allow_vote_change: false,
+            min_tokens_to_create_proposal: 0,
+            max_active_proposals_per_creator: 0,
+            max_voting_period,
+            latest_voting_start_time,
+            bump: 0,
+        }
+    }
+
+    fn token_owner_record() -> TokenOwnerRecord {
+        TokenOwnerRecord {
+            owner: Pubkey::default(),
+            governing_token_deposit_amount: 0,
+            delegated_amounts: [0; MAX_DELEGATION_SCOPES],
+            outstanding_vote_count: 0,
+            active_proposal_count: 0,
+            checkpoints: Vec::new(),
+            bump: 0,
+        }
+    }
+
+    /// Records a checkpoint at each timestamp with the timestamp as its deposit
+    fn record_deposits(record: &mut TokenOwnerRecord, dao_config: &DaoConfig, timestamps: impl Iterator<Item = i64>) {
+        for timestamp in timestamps {
+            record
+                .record_checkpoint(timestamp, dao_config, |checkpoint| checkpoint.deposit_amount = timestamp as u64)
+                .unwrap();
+        }
+    }
+
+    fn checkpoint_timestamps(record: &TokenOwnerRecord) -> Vec<i64> {
+        record.checkpoints.iter().map(|checkpoint| checkpoint.timestamp).collect()
+    }
+
+    #[test]
+    fn lock_power_decays_linearly_to_unlock() {
+        assert_eq!(lock_voting_power(100, 1_000, 0, 1_000), 400);
+        assert_eq!(lock_voting_power(100, 1_000, 500, 1_000), 200);
+        assert_eq!(lock_voting_power(100, 1_000, 1_000, 1_000), 0);
+        assert_eq!(lock_voting_power(100, 1_000, 2_000, 1_000), 0);
+        // Remaining time is capped at the maximum lock duration
+        assert_eq!(lock_voting_power(100, 5_000, 0, 1_000), 400);
+        assert_eq!(lock_voting_power(100, 1_000, 0, 0), 0);
+    }
+
+    #[test]
+    fn weight_counts_checkpoints_strictly_before_voting_start() {
+        let dao_config = dao_config(100, 0);
+        let mut record = token_owner_record();
+        record_deposits(&mut record, &dao_config, [10, 20].into_iter());
+
+        assert_eq!(record.voter_weight_at(10, 0), 0);
+        assert_eq!(record.voter_weight_at(20, 0), 10);
+        assert_eq!(record.voter_weight_at(21, 0), 20);
+    }
+
+    #[test]
+    fn checkpoints_at_the_same_time_update_in_place() {
+        let dao_config = dao_config(100, 0);
+        let mut record = token_owner_record();
+        record_deposits(&mut record, &dao_config, [10, 10].into_iter());
+        record.record_checkpoint(10, &dao_config, |checkpoint| checkpoint.lock_amount = 5).unwrap();
+
+        assert_eq!(checkpoint_timestamps(&record), vec![10]);
+        assert_eq!(record.checkpoints[0].deposit_amount, 10);
+        assert_eq!(record.checkpoints[0].lock_amount, 5);
+    }
+
+    #[test]
+    fn oldest_checkpoint_is_pruned_once_outside_the_voting_window() {
+        // A proposal still pending keeps the checkpoint before the latest, and shortening the
+        // voting period keeps the widest window proposals may have opened with
+        let mut dao_config = dao_config(15, 100);
+        dao_config.voting_period = 1;
+        let mut record = token_owner_record();
+        record_deposits(&mut record, &dao_config, 1..=MAX_CHECKPOINTS as i64);
+
+        // At 17 a proposal that started at 2 is still open and snapshots the checkpoint at 1
+        assert_eq!(
+            record.record_checkpoint(17, &dao_config, |_| {}).unwrap_err(),
+            GovernanceError::CheckpointStillNeeded.into()
+        );
+        assert_eq!(record.checkpoints.len(), MAX_CHECKPOINTS);
+        assert_eq!(record.voter_weight_at(2, 0), 1);
+
+        // At 18 proposals start at 3 or later, which the checkpoint at 2 covers
+        record_deposits(&mut record, &dao_config, [18].into_iter());
+        assert_eq!(record.checkpoints.len(), MAX_CHECKPOINTS);
+        assert_eq!(record.checkpoints[0].timestamp, 2);
+        assert_eq!(record.voter_weight_at(3, 0), 2);
+    }
+
+    #[test]
+    fn checkpoint_before_latest_is_pruned_when_no_voting_start_follows_it() {
+        // The latest voting start at 15 snapshots the checkpoint at 14
+        let dao_config = dao_config(100, 15);
+        let mut record = token_owner_record();
+        record_deposits(&mut record, &dao_config, 1..=MAX_CHECKPOINTS as i64);
+        record_deposits(&mut record, &dao_config, [17].into_iter());
+
+        let mut expected: Vec<i64> = (1..=14).collect();
+        expected.extend([16, 17]);
+        assert_eq!(checkpoint_timestamps(&record), expected);
+        assert_eq!(record.voter_weight_at(15, 0), 14);
+        assert_eq!(record.voter_weight_at(17, 0), 16);
+    }
+
+    #[test]
+    fn action_scope_requires_a_single_action_kind() {
+        let mut proposal = proposal(VoteType::YesNo, 0);
+        assert!(proposal.action_scope().is_none());
+
+        proposal.action_kind_counts[ActionKind::Treasury as usize] = 2;
+        assert!(proposal.action_scope() == Some(ActionKind::Treasury));
+
+        proposal.action_kind_counts[ActionKind::Config as usize] = 1;
+        assert!(proposal.action_scope().is_none());
+    }
+
+    #[test]
+    fn tally_without_votes_is_defeated() {
+        let proposal = proposal(VoteType::YesNo, 0);
+        assert!(proposal.tally(0, 0, 0) == (ProposalStatus::Defeated, 0));
+        assert!(proposal.tally(1_000, 10, 50) == (ProposalStatus::Defeated, 0));
+    }
+
+    #[test]
+    fn tally_requires_quorum_and_threshold() {
+        // 1,000 tokens carry at most 4,000 weight, so a 10% quorum needs 400
+        let mut proposal = proposal(VoteType::YesNo, 0);
+        proposal.add_votes(VoteOption::For, 300);
+        proposal.add_votes(VoteOption::Against, 99);
+        assert!(proposal.tally(1_000, 10, 50) == (ProposalStatus::Defeated, 0));
+
+        proposal.add_votes(VoteOption::Abstain, 1);
+        assert!(proposal.tally(1_000, 10, 50) == (ProposalStatus::Succeeded, 0));
+
+        proposal.add_votes(VoteOption::Against, 202);
+        assert!(proposal.tally(1_000, 10, 50) == (ProposalStatus::Defeated, 0));
+    }
+
+    #[test]
+    fn threshold_ignores_abstentions() {
+        let mut proposal = proposal(VoteType::YesNo, 0);
+        proposal.add_votes(VoteOption::For, 60);
+        proposal.add_votes(VoteOption::Against, 40);
+        proposal.add_votes(VoteOption::Abstain, 900);
+        assert!(proposal.tally(1_000, 25, 60) == (ProposalStatus::Succeeded, 0));
+        assert!(proposal.tally(1_000, 25, 61) == (ProposalStatus::Defeated, 0));
+    }
+
+    #[test]
+    fn quorum_counts_against_maximum_voter_weight() {
+        let mut proposal = proposal(VoteType::YesNo, 0);
+        proposal.add_votes(VoteOption::For, 1_000);
+        assert!(proposal.participation_reached(1_000, 25));
+        assert!(!proposal.participation_reached(1_000, 26));
+        assert!(proposal.tally(1_000, 50, 50) == (ProposalStatus::Defeated, 0));
+    }
+
+    #[test]
+    fn tally_picks_unique_leading_option() {
+        let mut proposal = proposal(VoteType::SingleChoice, 3);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b100 }, 50);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b001 }, 30);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b010 }, 20);
+        assert!(proposal.tally(100, 10, 50) == (ProposalStatus::Succeeded, 2));
+        assert!(proposal.tally(100, 10, 51) == (ProposalStatus::Defeated, 0));
+    }
+
+    #[test]
+    fn tied_leading_options_are_defeated() {
+        let mut proposal = proposal(VoteType::SingleChoice, 3);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b001 }, 40);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b010 }, 40);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b100 }, 20);
+        assert!(proposal.tally(100, 10, 0) == (ProposalStatus::Defeated, 0));
+    }
+
+    #[test]
+    fn votes_must_match_the_vote_type() {
+        let yes_no = proposal(VoteType::YesNo, 0);
+        assert!(yes_no.is_valid_vote(VoteOption::For));
+        assert!(yes_no.is_valid_vote(VoteOption::Abstain));
+        assert!(!yes_no.is_valid_vote(VoteOption::Choices { bitmap: 0b1 }));
+
+        let single_choice = proposal(VoteType::SingleChoice, 3);
+        assert!(!single_choice.is_valid_vote(VoteOption::For));
+        assert!(single_choice.is_valid_vote(VoteOption::Choices { bitmap: 0b100 }));
+        assert!(!single_choice.is_valid_vote(VoteOption::Choices { bitmap: 0b101 }));
+        assert!(!single_choice.is_valid_vote(VoteOption::Choices { bitmap: 0 }));
+        assert!(!single_choice.is_valid_vote(VoteOption::Choices { bitmap: 0b1000 }));
+    }
+
+    #[test]
+    fn approval_bitmaps_select_options_within_range() {
+        let approval = proposal(VoteType::Approval, MAX_OPTIONS);
+        assert!(approval.is_valid_vote(VoteOption::Choices { bitmap: 0b101 }));
+        assert!(approval.is_valid_vote(VoteOption::Choices { bitmap: (1 << MAX_OPTIONS) - 1 }));
+        assert!(!approval.is_valid_vote(VoteOption::Choices { bitmap: 1 << MAX_OPTIONS }));
+        assert!(!approval.is_valid_vote(VoteOption::Choices { bitmap: 0 }));
+    }
+
+    #[test]
+    fn approval_votes_count_once_toward_participation() {
+        let mut proposal = proposal(VoteType::Approval, 3);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b101 }, 10);
+        proposal.add_votes(VoteOption::Choices { bitmap: 0b011 }, 4);
+
+        let weights: Vec<u64> = proposal.options.iter().map(|option| option.vote_weight).collect();
+        assert_eq!(weights, vec![14, 4, 10]);
+        assert_eq!(proposal.choice_votes, 14);
+        assert!(proposal.tally(14, 25, 50) == (ProposalStatus::Succeeded, 0));
+
+        proposal.remove_votes(VoteOption::Choices { bitmap: 0b101 }, 10);
+        let weights: Vec<u64> = proposal.options.iter().map(|option| option.vote_weight).collect();
+        assert_eq!(weights, vec![4, 4, 0]);
+        assert_eq!(proposal.choice_votes, 4);
+        assert!(proposal.tally(14, 0, 0) == (ProposalStatus::Defeated, 0));
+    }
+
+    #[test]
+    fn multiple_choice_without_votes_is_defeated() {
+        let proposal = proposal(VoteType::Approval, 2);
+        assert!(proposal.tally(0, 0, 0) == (ProposalStatus::Defeated, 0));
+    }
+}
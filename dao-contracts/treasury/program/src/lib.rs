@@ -10,9 +10,11 @@ pub mod treasury {
 
     pub fn initialize_treasury(
         ctx: Context<InitializeTreasury>,
+        governance: Pubkey,
     ) -> Result<()> {
         let treasury = &mut ctx.accounts.treasury;
         treasury.authority = ctx.accounts.authority.key();
+        treasury.governance = governance;
         treasury.bump = *ctx.bumps.get("treasury").unwrap();
         Ok(())
     }
@@ -41,9 +43,9 @@ pub mod treasury {
         ctx: Context<WithdrawFunds>,
         amount: u64,
     ) -> Result<()> {
-        // Verify the governance program's authority
+        // Verify the withdrawal was signed by the governing DAO's PDA
         require!(
-            ctx.accounts.governance_program.key() == ctx.accounts.treasury.governance_program,
+            ctx.accounts.governance.key() == ctx.accounts.treasury.governance,
             TreasuryError::Unauthorized
         );
 
@@ -122,8 +124,8 @@ pub struct WithdrawFunds<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
     
-    /// CHECK: This is the governance program that authorized the withdrawal
-    pub governance_program: AccountInfo<'info>,
+    /// The governing DAO's PDA, signing through a governance program CPI
+    pub governance: Signer<'info>,
     
     /// CHECK: This is the recipient of the funds
     pub recipient: AccountInfo<'info>,
@@ -147,14 +149,14 @@ pub struct WithdrawFunds<'info> {
 #[account]
 pub struct Treasury {
     pub authority: Pubkey,
-    pub governance_program: Pubkey,
+    pub governance: Pubkey,
     pub bump: u8,
 }
 
 impl Treasury {
     pub const LEN: usize = 8 + // discriminator
         32 + // authority
-        32 + // governance_program
+        32 + // governance
         1; // bump
 }
 
@@ -5,6 +5,15 @@ import { getConnection } from './solana'
 
 const GOVERNANCE_PROGRAM_ID = new PublicKey('Govz1VyoyU5Bqe9Xe36hpYT2v7FqCaVQFZ4n2M2XzP1')
 
+// Each DAO hosted by the governance program is keyed by its governing token mint and the
+// authority that created it, so several DAOs can share one token
+const GOVERNING_TOKEN_MINT = process.env.NEXT_PUBLIC_GOVERNING_TOKEN_MINT
+  ? new PublicKey(process.env.NEXT_PUBLIC_GOVERNING_TOKEN_MINT)
+  : PublicKey.default
+const DAO_AUTHORITY = process.env.NEXT_PUBLIC_DAO_AUTHORITY
+  ? new PublicKey(process.env.NEXT_PUBLIC_DAO_AUTHORITY)
+  : PublicKey.default
+
 // Flag to use mock data when contracts aren't deployed
 const USE_MOCK_DATA = true
 let mockDelegations = [
@@ -22,6 +31,48 @@ let mockDelegations = [
   }
 ];
 
+function getDaoConfigPda(program: Program) {
+  const [daoConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('dao_config'), GOVERNING_TOKEN_MINT.toBuffer(), DAO_AUTHORITY.toBuffer()],
+    program.programId
+  )
+  return daoConfigPda
+}
+
+function getTokenOwnerRecordPda(program: Program, owner: PublicKey) {
+  const [tokenOwnerRecordPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('token_owner_record'), getDaoConfigPda(program).toBuffer(), owner.toBuffer()],
+    program.programId
+  )
+  return tokenOwnerRecordPda
+}
+
+// General delegations use scope index 0; scoped ones use their action kind + 1
+const GENERAL_DELEGATION_SCOPE = 0
+
+function getDelegationPda(program: Program, delegator: PublicKey, scopeIndex = GENERAL_DELEGATION_SCOPE) {
+  const [delegationPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('delegation'), getDaoConfigPda(program).toBuffer(), delegator.toBuffer(), Buffer.from([scopeIndex])],
+    program.programId
+  )
+  return delegationPda
+}
+
+// Outgoing delegations starting at the delegatee, used on-chain to reject cycles
+async function getDelegationChain(program: Program, delegatee: PublicKey) {
+  const daoConfig: any = await program.account.daoConfig.fetch(getDaoConfigPda(program))
+  const chain = []
+  let current = delegatee
+  for (let hop = 0; hop < daoConfig.maxDelegationDepth; hop++) {
+    const delegationPda = getDelegationPda(program, current)
+    chain.push({ pubkey: delegationPda, isSigner: false, isWritable: false })
+    const delegation: any = await program.account.delegation.fetchNullable(delegationPda)
+    if (!delegation) break
+    current = delegation.delegatee
+  }
+  return chain
+}
+
 export async function getGovernanceProgram(wallet: any) {
   const connection = getConnection()
   const provider = new AnchorProvider(connection, wallet, {})
@@ -47,23 +98,17 @@ export async function createDelegation(
     return newId
   }
 
-  const [delegationPda] = PublicKey.findProgramAddressSync(
-    [
-      Buffer.from('delegation'),
-      program.provider.publicKey!.toBuffer(),
-      delegatee.toBuffer(),
-    ],
-    program.programId
-  )
-
   return program.methods
-    .delegateVotes(new BN(amount))
+    .delegateVotes(new BN(amount), null, null)
     .accounts({
-      delegation: delegationPda,
+      delegation: getDelegationPda(program, program.provider.publicKey!),
+      tokenOwnerRecord: getTokenOwnerRecordPda(program, program.provider.publicKey!),
+      daoConfig: getDaoConfigPda(program),
       delegator: program.provider.publicKey,
       delegatee,
       systemProgram: SystemProgram.programId,
     })
+    .remainingAccounts(await getDelegationChain(program, delegatee))
     .rpc()
 }
 
@@ -81,6 +126,7 @@ export async function revokeDelegation(
     .revokeDelegation()
     .accounts({
       delegation: delegationId,
+      tokenOwnerRecord: getTokenOwnerRecordPda(program, program.provider.publicKey!),
       delegator: program.provider.publicKey,
     })
     .rpc()
@@ -95,6 +141,12 @@ export async function getDelegations(program: Program) {
     {
       memcmp: {
         offset: 8, // Skip discriminator
+        bytes: getDaoConfigPda(program).toBase58(),
+      },
+    },
+    {
+      memcmp: {
+        offset: 40, // Skip discriminator and dao
         bytes: program.provider.publicKey!.toBase58(),
       },
     },